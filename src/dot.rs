@@ -0,0 +1,99 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::graph::*;
+use crate::machine::*;
+use crate::parallel::*;
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> Machine<A, S, C> {
+    /// Render the graph of the machine, discovered from `actions`, in Graphviz DOT. Final
+    /// states have a double border and parallel regions are drawn as clusters.
+    pub fn to_dot(&self, actions: &[A]) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(&self.id));
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=rounded];\n");
+        write_graph(&mut dot, &self.graph(actions), &self.id, 1);
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> ParallelMachine<A, S, C> {
+    /// Render the graph of every region, discovered from `actions`, in Graphviz DOT. Each
+    /// region is drawn as a cluster.
+    pub fn to_dot(&self, actions: &[A]) -> String {
+        let mut dot = format!("digraph {} {{\n", quote(&self.id));
+        dot.push_str("    rankdir=LR;\n");
+        dot.push_str("    node [shape=box, style=rounded];\n");
+        for graph in self.graphs(actions).iter() {
+            write_region(&mut dot, graph, &self.id, 1);
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+/// Quote and escape an identifier or a label
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Identifier of the node of `state` in the machine at `path`
+fn node<S: Debug>(path: &str, state: &S) -> String {
+    quote(&format!("{}.{:?}", path, state))
+}
+
+/// Write the nodes and edges of a graph, naming nodes after the machine at `path`
+fn write_graph<S: Debug>(dot: &mut String, graph: &StateGraph<S>, path: &str, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let initial = quote(&format!("{}.[*]", path));
+    dot.push_str(&format!("{}{} [shape=point];\n", indent, initial));
+    dot.push_str(&format!("{}{} -> {};\n", indent, initial, node(path, &graph.initial)));
+
+    for state in graph.states.iter() {
+        let label = quote(&format!("{:?}", state.state));
+        let peripheries = if state.final_state { ", peripheries=2" } else { "" };
+        let line = format!("{} [label={}{}];\n", node(path, &state.state), label, peripheries);
+
+        if state.regions.is_empty() {
+            dot.push_str(&format!("{}{}", indent, line));
+            continue;
+        }
+
+        // A compound state is a cluster holding its own node and a cluster per region
+        let state_path = format!("{}.{:?}", path, state.state);
+        dot.push_str(&format!("{}subgraph {} {{\n", indent, quote(&cluster(&state_path))));
+        dot.push_str(&format!("{}    label={};\n", indent, label));
+        dot.push_str(&format!("{}    {}", indent, line));
+        for region in state.regions.iter() {
+            write_region(dot, region, &state_path, depth + 1);
+        }
+        dot.push_str(&format!("{}}}\n", indent));
+    }
+
+    for edge in graph.edges.iter() {
+        dot.push_str(&format!(
+            "{}{} -> {} [label={}];\n",
+            indent,
+            node(path, &edge.from),
+            node(path, &edge.to),
+            quote(&edge.events.join(", "))
+        ));
+    }
+}
+
+/// Write a region as a cluster labelled with its id
+fn write_region<S: Debug>(dot: &mut String, graph: &StateGraph<S>, path: &str, depth: usize) {
+    let indent = "    ".repeat(depth);
+    let region_path = format!("{}.{}", path, graph.id);
+    dot.push_str(&format!("{}subgraph {} {{\n", indent, quote(&cluster(&region_path))));
+    dot.push_str(&format!("{}    label={};\n", indent, quote(&graph.id)));
+    dot.push_str(&format!("{}    style=dashed;\n", indent));
+    write_graph(dot, graph, &region_path, depth + 1);
+    dot.push_str(&format!("{}}}\n", indent));
+}
+
+/// Graphviz only draws subgraphs as clusters when their name starts with `cluster`
+fn cluster(path: &str) -> String {
+    format!("cluster_{}", path)
+}
@@ -0,0 +1,73 @@
+use std::error;
+use std::fmt;
+
+/// Errors returned by state machines
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// A hook panicked while the machine with the given id handled an action
+    Panicked(String),
+
+    /// The machine with the given id entered a state whose invariant does not hold
+    InvariantViolated(String),
+
+    /// The given number of history entries were evicted, so the history cannot be replayed
+    HistoryEvicted(usize),
+
+    /// No node with the given index exists in the undo tree
+    NodeNotFound(usize),
+
+    /// No checkpoint with the given name exists
+    CheckpointNotFound(String),
+
+    /// The given position is past the end of the history
+    IndexOutOfRange(usize),
+
+    /// A snapshot does not match the definition of the machine with the given id
+    SnapshotMismatch(String),
+
+    /// No migration upgrades snapshots written with the given schema version
+    MigrationNotFound(u32),
+
+    /// A snapshot was written with the given schema version, newer than the current one
+    UnsupportedVersion(u32),
+
+    /// A snapshot could not be read, with the reason given by the deserializer
+    InvalidSnapshot(String),
+
+    /// An xstate config could not be imported or exported, with the reason
+    InvalidConfig(String),
+
+    /// A region with the given id already exists in the parallel machine
+    DuplicateRegion(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Panicked(id) => write!(f, "machine `{}` panicked during a transition", id),
+            Error::InvariantViolated(id) => {
+                write!(f, "machine `{}` violated a state invariant", id)
+            }
+            Error::HistoryEvicted(count) => {
+                write!(f, "{} history entries were evicted and cannot be replayed", count)
+            }
+            Error::NodeNotFound(node) => write!(f, "undo tree has no node {}", node),
+            Error::CheckpointNotFound(name) => write!(f, "no checkpoint named `{}`", name),
+            Error::IndexOutOfRange(index) => write!(f, "history has no position {}", index),
+            Error::SnapshotMismatch(id) => {
+                write!(f, "snapshot does not match the definition of machine `{}`", id)
+            }
+            Error::MigrationNotFound(version) => {
+                write!(f, "no migration from snapshot version {}", version)
+            }
+            Error::UnsupportedVersion(version) => {
+                write!(f, "snapshot version {} is newer than the schema", version)
+            }
+            Error::InvalidSnapshot(reason) => write!(f, "invalid snapshot: {}", reason),
+            Error::InvalidConfig(reason) => write!(f, "invalid xstate config: {}", reason),
+            Error::DuplicateRegion(id) => write!(f, "a region named `{}` already exists", id),
+        }
+    }
+}
+
+impl error::Error for Error {}
@@ -0,0 +1,167 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::machine::*;
+use crate::parallel::*;
+
+/// States and transitions of a machine, as drawn by the diagram exporters
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateGraph<S> {
+    /// Identifier of the machine
+    pub id: String,
+
+    pub initial: S,
+
+    /// Every state, in the order they were discovered from the initial state
+    pub states: Vec<StateNode<S>>,
+
+    pub edges: Vec<StateEdge<S>>,
+}
+
+/// A state in the graph of a machine
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateNode<S> {
+    pub state: S,
+    pub final_state: bool,
+
+    /// Whether the state has an `on_entry` and an `on_exit` hook
+    pub entry: bool,
+    pub exit: bool,
+
+    /// Names given to the `on_entry` and `on_exit` hooks
+    pub entry_name: Option<&'static str>,
+    pub exit_name: Option<&'static str>,
+
+    pub description: Option<&'static str>,
+    pub note: Option<&'static str>,
+
+    /// Graphs of the parallel regions nested in the state
+    pub regions: Vec<StateGraph<S>>,
+}
+
+/// Transitions between two states, labelled by the events that trigger them
+#[derive(Debug, Clone, PartialEq)]
+pub struct StateEdge<S> {
+    pub from: S,
+    pub to: S,
+    pub events: Vec<String>,
+}
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> Machine<A, S, C> {
+    /// Discover the graph of the machine by sending each of `actions` to every state with the
+    /// current context. Guards are ignored, so the graph holds every transition that could be
+    /// taken. Events are named after the `Debug` output of the actions.
+    pub fn graph(&self, actions: &[A]) -> StateGraph<S> {
+        let definition = &self.definition;
+        let mut states = vec![definition.initial];
+        let mut edges: Vec<StateEdge<S>> = vec![];
+
+        let mut next = 0;
+        loop {
+            while let Some(&state) = states.get(next) {
+                next += 1;
+                for (event, target) in self.outgoing(state, actions) {
+                    add_edge(&mut edges, state, target, event);
+                    if !states.contains(&target) {
+                        states.push(target);
+                    }
+                }
+            }
+
+            // Continue from the first unreachable state, so the order stays stable
+            let unreachable = definition
+                .states
+                .keys()
+                .filter(|state| !states.contains(state))
+                .min_by_key(|state| format!("{:?}", state));
+            match unreachable {
+                Some(state) => states.push(*state),
+                None => break,
+            }
+        }
+
+        let states = states
+            .into_iter()
+            .map(|state| {
+                let transition = definition.states.get(&state).copied().unwrap_or_default();
+                StateNode {
+                    state,
+                    final_state: transition.final_state,
+                    entry: transition.on_entry.is_some(),
+                    exit: transition.on_exit.is_some(),
+                    entry_name: transition.entry_name,
+                    exit_name: transition.exit_name,
+                    description: transition.description,
+                    note: transition.note,
+                    regions: match self.regions.get(&state) {
+                        Some(regions) => regions.graphs(actions),
+                        None => vec![],
+                    },
+                }
+            })
+            .collect();
+
+        StateGraph { id: self.id.clone(), initial: definition.initial, states, edges }
+    }
+
+    /// Events leaving `state` with the state each one leads to
+    fn outgoing(&self, state: S, actions: &[A]) -> Vec<(String, S)> {
+        let definition = &self.definition;
+        let mut outgoing = vec![];
+
+        if let Some(transition) = definition.states.get(&state) {
+            if let Some(fn_on) = transition.on {
+                for action in actions.iter() {
+                    let target = fn_on(self.context, *action, state);
+                    if target != state {
+                        outgoing.push((format!("{:?}", action), target));
+                    }
+                }
+            }
+
+            if let (Some(fn_on_done), Some(regions)) =
+                (transition.on_done, self.regions.get(&state))
+            {
+                let event = regions.done_event();
+                let target = fn_on_done(self.context, &event, state);
+                if target != state {
+                    outgoing.push((event, target));
+                }
+            }
+        }
+
+        #[cfg(feature = "xstate")]
+        for transition in definition.events.get(&state).into_iter().flatten() {
+            outgoing.push((format!("{:?}", transition.event), transition.target));
+        }
+        outgoing
+    }
+}
+
+/// Add `event` to the edge between `from` and `to`, creating the edge if needed
+fn add_edge<S: PartialEq>(edges: &mut Vec<StateEdge<S>>, from: S, to: S, event: String) {
+    match edges.iter_mut().find(|edge| edge.from == from && edge.to == to) {
+        Some(edge) if !edge.events.contains(&event) => edge.events.push(event),
+        Some(_) => {}
+        None => edges.push(StateEdge { from, to, events: vec![event] }),
+    }
+}
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> ParallelMachine<A, S, C> {
+    /// Discover the graph of every region, in order
+    pub fn graphs(&self, actions: &[A]) -> Vec<StateGraph<S>> {
+        self.machines.iter().map(|machine| machine.graph(actions)).collect()
+    }
+}
+
+/// Identifier made of `path`, keeping only the letters, digits and underscores that Mermaid
+/// and PlantUML allow in identifiers
+pub(crate) fn identifier(path: &str) -> String {
+    path.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect()
+}
+
+/// Identifier of `state` in the machine at `path`. States are prefixed by their machine, since
+/// the diagrams merge states of different regions sharing a name.
+pub(crate) fn state_id<S: Debug>(path: &str, state: &S) -> String {
+    identifier(&format!("{}.{:?}", path, state))
+}
@@ -1,55 +1,643 @@
-use std::hash::Hash;
+use std::collections::VecDeque;
+use std::fmt;
 use std::fmt::Debug;
+use std::mem;
+use std::time::{Duration, SystemTime};
 
+use crate::error::*;
 use crate::machine::*;
+use crate::state_machine::*;
 
-#[derive(Debug)]
-pub struct HistoryMachine<A, S, C> {
-    pub machine: Machine<A, S, C>,
-    pub past: Vec<(S, C)>,
-    pub future: Vec<(S, C)>,
+/// Estimate of the memory used by a value, used to bound the history of a machine
+pub trait EstimateSize {
+    /// Approximate size of the value in bytes, including any heap allocations
+    fn estimated_size(&self) -> usize {
+        mem::size_of_val(self)
+    }
+}
+
+/// Statistics on entries evicted from a bounded history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryStats {
+    /// Number of entries evicted
+    pub evicted: usize,
+
+    /// Estimated bytes released by evicted entries
+    pub evicted_bytes: usize,
 }
 
-impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> HistoryMachine<A, S, C> {
+/// What happens to the steps that can be redone when a new action is sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RedoPolicy {
+    /// Drop the redo stack, since its states are no longer reachable from the current one
+    #[default]
+    Discard,
+
+    /// Keep the redo stack, so redo may jump to a state the new action did not lead to
+    Keep,
+}
+
+/// A step in the history of a machine, holding snapshots of type `T`
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HistoryEntry<A, T> {
+    /// Monotonic sequence number of the action
+    pub seq: u64,
+
+    /// When the action was sent
+    pub timestamp: SystemTime,
+
+    /// The action that caused the step. `None` when the step restored a checkpoint.
+    pub action: Option<A>,
+
+    /// Entries sharing a group are undone and redone together
+    pub group: u64,
+
+    /// Snapshot of the machine to return to when the entry is undone or redone
+    pub snapshot: T,
+
+    /// Snapshot the step restored from a checkpoint. `None` for actions.
+    pub restored: Option<T>,
+
+    /// Actions sent after this step that `filter` or `skip_unchanged` kept from being recorded.
+    /// They are not undo steps, but `replay` sends them again after the step.
+    #[cfg_attr(feature = "serde", serde(default = "Vec::new"))]
+    pub unrecorded: Vec<A>,
+}
+
+/// A position in the timeline of a machine
+#[derive(Debug, Clone, Copy)]
+pub struct TimelineEntry<A, S, C> {
+    /// Position in the timeline, `0` being the oldest
+    pub index: usize,
+
+    /// The action that led to this position. `None` for the oldest position and restores.
+    pub action: Option<A>,
+
+    pub value: S,
+    pub context: C,
+}
+
+/// Change notified to the listeners of a machine
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HistoryEvent<S, C> {
+    /// A step was undone, restoring this state and context
+    Undo(S, C),
+
+    /// A step was redone, restoring this state and context
+    Redo(S, C),
+
+    /// The machine moved to another position of the timeline, with this state and context
+    Goto(S, C),
+
+    /// A checkpoint was restored, with this state and context
+    Restore(S, C),
+}
+
+/// Function called with every change notified by a history machine
+pub type Listener<S, C> = Box<dyn FnMut(&HistoryEvent<S, C>)>;
+
+/// A named snapshot to return to
+#[derive(Debug, Clone)]
+pub struct Checkpoint<T> {
+    pub name: String,
+    pub snapshot: T,
+}
+
+/// Record the history of a machine so it can be undone and redone. Wraps a `Machine` by
+/// default, or any other `StateMachine` whose value is `S` and context is `C`.
+pub struct HistoryMachine<A, S, C, M = Machine<A, S, C>>
+where
+    M: StateMachine<A>,
+{
+    pub machine: M,
+    pub past: VecDeque<HistoryEntry<A, M::Snapshot>>,
+    pub future: Vec<HistoryEntry<A, M::Snapshot>>,
+
+    /// Maximum number of entries kept in `past`. The oldest entries are evicted first.
+    pub capacity: Option<usize>,
+
+    /// Maximum estimated bytes kept in `past`. The oldest entries are evicted first.
+    pub memory_limit: Option<usize>,
+
+    /// What happens to `future` when a new action is sent
+    pub redo_policy: RedoPolicy,
+
+    /// Merge actions sent within this window of the previous one into a single undo step
+    pub coalesce: Option<Duration>,
+
+    /// Decide from the action and the states before and after it whether a transition is
+    /// recorded. Transitions that are not recorded still apply `redo_policy` when they change
+    /// the state, since the steps to redo no longer follow from it.
+    pub filter: Option<fn(action: A, from: S, to: S) -> bool>,
+
+    /// Run the `on_exit` and `on_entry` hooks of the states left and entered by undo and redo.
+    /// The contexts returned by the hooks are discarded, so the restored context stays exact.
+    pub run_hooks: bool,
+
+    /// Compare contexts to skip transitions that changed neither state nor context
+    unchanged: Option<fn(&C, &C) -> bool>,
+
+    listeners: Vec<Listener<S, C>>,
+
+    /// Statistics on entries evicted to respect `capacity` and `memory_limit`
+    pub stats: HistoryStats,
+
+    checkpoints: Vec<Checkpoint<M::Snapshot>>,
+
+    /// Group of the transaction in progress
+    group: Option<u64>,
+
+    /// Number of nested `begin_group` calls that have not been ended yet
+    group_depth: usize,
+
+    /// Group ended by the last `end_group`, which coalescing must not extend
+    closed_group: Option<u64>,
+
+    /// Estimated bytes currently held by `past`
+    memory: usize,
+
+    /// Sequence number of the next recorded action
+    seq: u64,
+
+    size_of: fn(&C) -> usize,
+}
+
+impl<A, S, C, M> Debug for HistoryMachine<A, S, C, M>
+where
+    A: Debug,
+    M: StateMachine<A> + Debug,
+    M::Snapshot: Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HistoryMachine")
+            .field("machine", &self.machine)
+            .field("past", &self.past)
+            .field("future", &self.future)
+            .field("capacity", &self.capacity)
+            .field("memory_limit", &self.memory_limit)
+            .field("redo_policy", &self.redo_policy)
+            .field("coalesce", &self.coalesce)
+            .field("run_hooks", &self.run_hooks)
+            .field("listeners", &self.listeners.len())
+            .field("stats", &self.stats)
+            .field("checkpoints", &self.checkpoints)
+            .field("group", &self.group)
+            .field("group_depth", &self.group_depth)
+            .field("closed_group", &self.closed_group)
+            .field("memory", &self.memory)
+            .field("seq", &self.seq)
+            .finish()
+    }
+}
+
+impl<A, S, C, M> HistoryMachine<A, S, C, M>
+where
+    A: Copy,
+    S: Clone + PartialEq,
+    C: Clone,
+    M: StateMachine<A, Value = S, Context = C>,
+{
     /// Create a new state machine
-    pub fn new(machine: Machine<A, S, C>) -> Self {
-        HistoryMachine { machine, future: vec![], past: vec![] }
+    pub fn new(machine: M) -> Self {
+        HistoryMachine {
+            machine,
+            future: vec![],
+            past: VecDeque::new(),
+            capacity: None,
+            memory_limit: None,
+            redo_policy: RedoPolicy::default(),
+            coalesce: None,
+            filter: None,
+            run_hooks: false,
+            unchanged: None,
+            listeners: vec![],
+            stats: HistoryStats::default(),
+            checkpoints: vec![],
+            group: None,
+            group_depth: 0,
+            closed_group: None,
+            memory: 0,
+            seq: 0,
+            size_of: mem::size_of_val,
+        }
+    }
+
+    /// Create a new state machine that keeps at most `capacity` entries to undo
+    pub fn with_capacity(machine: M, capacity: usize) -> Self {
+        let mut history = HistoryMachine::new(machine);
+        history.capacity = Some(capacity);
+        history
     }
 
     /// Send an action to the state machines
     pub fn transition(&mut self, action: &A) {
-        self.past.push((
-                self.machine.value,
-                self.machine.context
-        ));
+        let timestamp = SystemTime::now();
+        let snapshot = self.machine.snapshot();
+        let (value, context) = M::snapshot_parts(&snapshot);
         self.machine.transition(action);
+
+        if let Some(fn_filter) = self.filter {
+            if !fn_filter(*action, value.clone(), self.machine.value()) {
+                if value != self.machine.value() && self.redo_policy == RedoPolicy::Discard {
+                    self.future.clear();
+                }
+                self.log_unrecorded(*action);
+                return;
+            }
+        }
+
+        if let Some(fn_context_eq) = self.unchanged {
+            if value == self.machine.value() && fn_context_eq(&context, &self.machine.context()) {
+                self.log_unrecorded(*action);
+                return;
+            }
+        }
+
+        self.record(Some(*action), None, timestamp, snapshot);
+    }
+
+    /// Save the current state and context under `name`, replacing any checkpoint with the
+    /// same name
+    pub fn checkpoint(&mut self, name: &str) {
+        let checkpoint = Checkpoint { name: name.to_string(), snapshot: self.machine.snapshot() };
+
+        match self.checkpoints.iter_mut().find(|checkpoint| checkpoint.name == name) {
+            Some(existing) => *existing = checkpoint,
+            None => self.checkpoints.push(checkpoint),
+        }
+    }
+
+    /// Return to the checkpoint saved under `name`. The restore is recorded as a step of its
+    /// own, so it can be undone.
+    pub fn restore(&mut self, name: &str) -> Result<(), Error> {
+        let checkpoint = self.checkpoints.iter().find(|checkpoint| checkpoint.name == name);
+        let snapshot = match checkpoint {
+            Some(checkpoint) => checkpoint.snapshot.clone(),
+            None => return Err(Error::CheckpointNotFound(name.to_string())),
+        };
+
+        let current = self.machine.snapshot();
+        self.record(None, Some(snapshot.clone()), SystemTime::now(), current);
+        self.machine.restore_snapshot(snapshot);
+        self.notify(HistoryEvent::Restore(self.machine.value(), self.machine.context()));
+        Ok(())
+    }
+
+    /// Saved checkpoints, in the order they were first created
+    pub fn checkpoints(&self) -> &[Checkpoint<M::Snapshot>] {
+        &self.checkpoints
+    }
+
+    /// Start grouping transitions, so they are undone and redone as a single step until the
+    /// matching `end_group`. Groups can be nested; only the outermost one counts.
+    pub fn begin_group(&mut self) {
+        if self.group_depth == 0 {
+            self.group = Some(self.seq);
+        }
+        self.group_depth += 1;
+    }
+
+    /// Stop grouping transitions started by `begin_group`
+    pub fn end_group(&mut self) {
+        self.group_depth = self.group_depth.saturating_sub(1);
+        if self.group_depth == 0 {
+            self.closed_group = self.group.take().or(self.closed_group);
+        }
+    }
+
+    /// Run `f` as a single undo step
+    pub fn transaction<R, F: FnOnce(&mut Self) -> R>(&mut self, f: F) -> R {
+        self.begin_group();
+        let result = f(self);
+        self.end_group();
+        result
+    }
+
+    /// Undo the last step, returning the restored state and context. Returns `None` when
+    /// there is nothing to undo.
+    pub fn undo(&mut self) -> Option<(S, C)> {
+        let group = self.past.back()?.group;
+        let previous_value = self.machine.value();
+
+        let mut action = None;
+        while self.past.back().is_some_and(|entry| entry.group == group) {
+            action = self.undo_step();
+        }
+
+        let (value, context) = (self.machine.value(), self.machine.context());
+        self.run_hooks(&previous_value, action);
+        self.notify(HistoryEvent::Undo(value.clone(), context.clone()));
+        Some((value, context))
     }
 
-    pub fn undo(&mut self) {
-        if self.past.len() > 0 {
-            if let Some((state, context)) = self.past.pop() {
-                self.future.push((
-                        self.machine.value,
-                        self.machine.context
-                ));
+    /// Redo the last undone step, returning the restored state and context. Returns `None`
+    /// when there is nothing to redo.
+    pub fn redo(&mut self) -> Option<(S, C)> {
+        let group = self.future.last()?.group;
+        let previous_value = self.machine.value();
 
-                self.machine.set_state(state);
-                self.machine.set_context(context);
+        let mut action = None;
+        while self.future.last().is_some_and(|entry| entry.group == group) {
+            action = self.redo_step();
+        }
+
+        let (value, context) = (self.machine.value(), self.machine.context());
+        self.run_hooks(&previous_value, action);
+        self.notify(HistoryEvent::Redo(value.clone(), context.clone()));
+        Some((value, context))
+    }
+
+    /// Whether there is a step to undo
+    pub fn can_undo(&self) -> bool {
+        !self.past.is_empty()
+    }
+
+    /// Whether there is a step to redo
+    pub fn can_redo(&self) -> bool {
+        !self.future.is_empty()
+    }
+
+    /// Call `listener` every time a step is undone or redone, the machine moves along the
+    /// timeline or a checkpoint is restored
+    pub fn subscribe(&mut self, listener: impl FnMut(&HistoryEvent<S, C>) + 'static) {
+        self.listeners.push(Box::new(listener));
+    }
+
+    /// Number of recorded steps, both undoable and redoable
+    pub fn len(&self) -> usize {
+        self.past.len() + self.future.len()
+    }
+
+    /// Whether there are no steps to undo or redo
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Position of the current state in the timeline, from `0` to `len()`
+    pub fn cursor(&self) -> usize {
+        self.past.len()
+    }
+
+    /// Move to any position of the timeline, one step at a time regardless of groups. Steps
+    /// redone beyond `capacity` or `memory_limit` evict the oldest entries, so the cursor may
+    /// end up before `index`.
+    pub fn goto(&mut self, index: usize) -> Result<(), Error> {
+        if index > self.len() {
+            return Err(Error::IndexOutOfRange(index));
+        }
+
+        let cursor = self.cursor();
+        for _ in index..cursor {
+            if self.past.is_empty() {
+                break;
+            }
+            self.undo_step();
+        }
+        for _ in cursor..index {
+            if self.future.is_empty() {
+                break;
+            }
+            self.redo_step();
+        }
+
+        self.notify(HistoryEvent::Goto(self.machine.value(), self.machine.context()));
+        Ok(())
+    }
+
+    /// State and context at a position of the timeline
+    pub fn at(&self, index: usize) -> Option<TimelineEntry<A, S, C>> {
+        if index > self.cursor() {
+            let entry = self.future.get(self.len().checked_sub(index)?)?;
+            let (value, context) = M::snapshot_parts(&entry.snapshot);
+            return Some(TimelineEntry { index, action: entry.action, value, context });
+        }
+
+        let action = index.checked_sub(1).and_then(|previous| self.past[previous].action);
+        let (value, context) = match self.past.get(index) {
+            Some(entry) => M::snapshot_parts(&entry.snapshot),
+            None => (self.machine.value(), self.machine.context()),
+        };
+        Some(TimelineEntry { index, action, value, context })
+    }
+
+    /// Every position of the timeline in order: past states, the current state, then the
+    /// states that can be redone
+    pub fn timeline(&self) -> impl Iterator<Item = TimelineEntry<A, S, C>> + '_ {
+        (0..=self.len()).filter_map(move |index| self.at(index))
+    }
+
+    /// Actions that led to the current state, oldest first
+    pub fn log(&self) -> impl Iterator<Item = &HistoryEntry<A, M::Snapshot>> {
+        self.past.iter()
+    }
+
+    /// Rebuild the current state by re-sending every logged action, including the actions that
+    /// were not recorded, starting from the state before the oldest entry. Restored checkpoints
+    /// are applied as they were recorded. With
+    /// `from_initial`, fails if entries were evicted so the log no longer reaches back to the
+    /// initial state of the machine.
+    pub fn replay(&mut self, from_initial: bool) -> Result<(), Error> {
+        if from_initial && self.stats.evicted > 0 {
+            return Err(Error::HistoryEvicted(self.stats.evicted));
+        }
+
+        if let Some(entry) = self.past.front() {
+            self.machine.restore_snapshot(entry.snapshot.clone());
+        }
+
+        for entry in self.past.iter() {
+            if let Some(action) = entry.action {
+                self.machine.transition(&action);
+            } else if let Some(snapshot) = entry.restored.clone() {
+                self.machine.restore_snapshot(snapshot);
+            }
+            for action in entry.unrecorded.iter() {
+                self.machine.transition(action);
+            }
+        }
+        Ok(())
+    }
+
+    /// Undo a single entry, returning its action
+    fn undo_step(&mut self) -> Option<A> {
+        let mut entry = self.pop_past()?;
+        let restored = mem::replace(&mut entry.snapshot, self.machine.snapshot());
+        let action = entry.action;
+
+        self.future.push(entry);
+        self.machine.restore_snapshot(restored);
+        action
+    }
+
+    /// Redo a single entry, returning its action
+    fn redo_step(&mut self) -> Option<A> {
+        let mut entry = self.future.pop()?;
+        let restored = mem::replace(&mut entry.snapshot, self.machine.snapshot());
+        let action = entry.action;
+
+        self.push_past(entry);
+        self.machine.restore_snapshot(restored);
+        action
+    }
+
+    /// Run the hooks of the states left and entered when moving away from `previous_value`
+    fn run_hooks(&self, previous_value: &S, action: Option<A>) {
+        if let Some(action) = action {
+            if self.run_hooks {
+                self.machine.run_hooks(previous_value, &action);
             }
         }
     }
 
-    pub fn redo(&mut self) {
-        if self.future.len() > 0 {
-            if let Some((state, context)) = self.future.pop() {
-                self.past.push((
-                        self.machine.value,
-                        self.machine.context
-                ));
+    /// Keep an action that is not recorded with the last step, so `replay` still sends it
+    fn log_unrecorded(&mut self, action: A) {
+        if let Some(entry) = self.past.back_mut() {
+            entry.unrecorded.push(action);
+        }
+    }
+
+    fn notify(&mut self, event: HistoryEvent<S, C>) {
+        for listener in self.listeners.iter_mut() {
+            listener(&event);
+        }
+    }
+
+    /// Push the snapshot from before a step caused by `action`, or by restoring a checkpoint
+    /// to `restored`, onto `past`
+    fn record(
+        &mut self,
+        action: Option<A>,
+        restored: Option<M::Snapshot>,
+        timestamp: SystemTime,
+        snapshot: M::Snapshot,
+    ) {
+        let group = match self.group {
+            Some(group) => group,
+            None => self.coalesced_group(action, timestamp).unwrap_or(self.seq),
+        };
+
+        self.push_past(HistoryEntry {
+            seq: self.seq,
+            timestamp,
+            action,
+            group,
+            snapshot,
+            restored,
+            unrecorded: vec![],
+        });
+        self.seq += 1;
+
+        if self.redo_policy == RedoPolicy::Discard {
+            self.future.clear();
+        }
+    }
+
+    /// Group of the previous action, if `action` arrived within the `coalesce` window
+    fn coalesced_group(&self, action: Option<A>, timestamp: SystemTime) -> Option<u64> {
+        let window = self.coalesce?;
+        action?;
+
+        let previous = self.past.back().filter(|entry| entry.action.is_some())?;
+        if previous.seq + 1 != self.seq
+            || !self.future.is_empty()
+            || self.closed_group == Some(previous.group)
+        {
+            return None;
+        }
+
+        match timestamp.duration_since(previous.timestamp) {
+            Ok(elapsed) if elapsed <= window => Some(previous.group),
+            _ => None,
+        }
+    }
+
+    /// Estimated bytes used by a single history entry
+    fn entry_size(&self, entry: &HistoryEntry<A, M::Snapshot>) -> usize {
+        let (_, context) = M::snapshot_parts(&entry.snapshot);
+        let size = mem::size_of::<HistoryEntry<A, M::Snapshot>>();
+        size.saturating_sub(mem::size_of::<C>()) + (self.size_of)(&context)
+    }
+
+    fn push_past(&mut self, entry: HistoryEntry<A, M::Snapshot>) {
+        self.memory += self.entry_size(&entry);
+        self.past.push_back(entry);
+
+        // Evict the oldest entries until the history fits its limits again
+        loop {
+            let over_capacity = self.capacity.is_some_and(|capacity| self.past.len() > capacity);
+            let over_memory = self.memory_limit.is_some_and(|limit| self.memory > limit);
+            if !over_capacity && !over_memory {
+                break;
+            }
 
-                self.machine.set_state(state);
-                self.machine.set_context(context);
+            match self.past.pop_front() {
+                Some(evicted) => {
+                    let size = self.entry_size(&evicted);
+                    self.memory = self.memory.saturating_sub(size);
+                    self.stats.evicted += 1;
+                    self.stats.evicted_bytes += size;
+                }
+                None => break,
             }
         }
     }
+
+    /// Replace the whole history, as when restoring a snapshot. Entries beyond `capacity` or
+    /// `memory_limit` are evicted, and any group in progress is ended.
+    #[cfg(feature = "serde")]
+    pub(crate) fn replace_entries(
+        &mut self,
+        past: Vec<HistoryEntry<A, M::Snapshot>>,
+        future: Vec<HistoryEntry<A, M::Snapshot>>,
+    ) {
+        let seq = past.iter().chain(future.iter()).map(|entry| entry.seq + 1).max();
+
+        self.past.clear();
+        self.memory = 0;
+        for entry in past {
+            self.push_past(entry);
+        }
+        self.future = future;
+        self.seq = seq.unwrap_or(0);
+        self.group = None;
+        self.group_depth = 0;
+        self.closed_group = None;
+    }
+
+    fn pop_past(&mut self) -> Option<HistoryEntry<A, M::Snapshot>> {
+        let entry = self.past.pop_back()?;
+        self.memory = self.memory.saturating_sub(self.entry_size(&entry));
+        Some(entry)
+    }
+}
+
+impl<A, S, C, M> HistoryMachine<A, S, C, M>
+where
+    A: Copy,
+    S: Clone + PartialEq,
+    C: Clone + EstimateSize,
+    M: StateMachine<A, Value = S, Context = C>,
+{
+    /// Create a new state machine that keeps at most `memory_limit` estimated bytes of entries
+    /// to undo, measuring contexts with `EstimateSize`
+    pub fn with_memory_limit(machine: M, memory_limit: usize) -> Self {
+        let mut history = HistoryMachine::new(machine);
+        history.memory_limit = Some(memory_limit);
+        history.size_of = C::estimated_size;
+        history
+    }
+}
+
+impl<A, S, C, M> HistoryMachine<A, S, C, M>
+where
+    A: Copy,
+    S: Clone + PartialEq,
+    C: Clone + PartialEq,
+    M: StateMachine<A, Value = S, Context = C>,
+{
+    /// Skip recording transitions that changed neither the state nor the context
+    pub fn skip_unchanged(&mut self, skip: bool) {
+        self.unchanged = if skip { Some(C::eq) } else { None };
+    }
 }
@@ -1,9 +1,32 @@
 //! A state machine library for Rust, inspired by [xstate](https://github.com/davidkpiano/xstate).
 
+pub mod error;
 pub mod machine;
 pub mod parallel;
+pub mod graph;
+pub mod dot;
+pub mod mermaid;
+pub mod plantuml;
+pub mod pool;
 pub mod history;
+pub mod region;
+#[cfg(feature = "serde")]
+pub mod snapshot;
+pub mod state_machine;
+pub mod undo_tree;
+#[cfg(feature = "xstate")]
+pub mod xstate;
 
+pub use self::error::*;
 pub use self::machine::*;
 pub use self::parallel::*;
+pub use self::graph::*;
 pub use self::history::*;
+pub use self::pool::*;
+pub use self::region::*;
+#[cfg(feature = "serde")]
+pub use self::snapshot::*;
+pub use self::state_machine::*;
+pub use self::undo_tree::*;
+#[cfg(feature = "xstate")]
+pub use self::xstate::*;
@@ -1,9 +1,101 @@
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::parallel::*;
+
+/// Immutable description of a state machine, shared by every running instance through an `Arc`
+#[derive(Debug, Clone)]
+pub struct MachineDefinition<A, S, C> {
+    /// Default identifier of the instances spawned from this definition
+    pub id: String,
+
+    /// Initial state of the instances
+    pub initial: S,
+
+    /// Available states and transitions for the machine.
+    pub states: HashMap<S, Transition<A, S, C>>,
+
+    /// Regions nested inside compound states, built for every instance when it is spawned
+    pub regions: HashMap<S, ParallelDefinition<A, S, C>>,
+
+    /// History pseudo-states. Entering one resumes the state the machine was last exited from.
+    pub history: HashMap<S, HistoryState<S>>,
+
+    /// Transitions declared as data, tried in order when a state has no `on` function
+    #[cfg(feature = "xstate")]
+    pub events: HashMap<S, Vec<EventTransition<A, S, C>>>,
+
+    /// Compare actions with the `event` of `events`
+    #[cfg(feature = "xstate")]
+    event_eq: Option<fn(&A, &A) -> bool>,
+}
+
+impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> MachineDefinition<A, S, C> {
+    /// Create an empty definition
+    pub fn new(id: String, initial: S) -> Self {
+        MachineDefinition {
+            id,
+            initial,
+            states: HashMap::new(),
+            regions: HashMap::new(),
+            history: HashMap::new(),
+            #[cfg(feature = "xstate")]
+            events: HashMap::new(),
+            #[cfg(feature = "xstate")]
+            event_eq: None,
+        }
+    }
+
+    pub fn add_state(&mut self, state_name: S, state: Transition<A, S, C>) {
+        self.states.insert(state_name, state);
+    }
+
+    /// Add a compound state whose children are a set of parallel regions
+    pub fn add_parallel_state(
+        &mut self,
+        state_name: S,
+        state: Transition<A, S, C>,
+        regions: ParallelDefinition<A, S, C>,
+    ) {
+        self.states.insert(state_name, state);
+        self.regions.insert(state_name, regions);
+    }
+
+    /// Add a history pseudo-state, resolved to another state whenever it is entered
+    pub fn add_history_state(&mut self, state_name: S, history: HistoryState<S>) {
+        self.history.insert(state_name, history);
+    }
+
+    /// First transition of `events` leaving `state` on `action` whose guard holds
+    #[cfg(feature = "xstate")]
+    pub(crate) fn event_transition(
+        &self,
+        context: C,
+        action: &A,
+        state: S,
+        active: &[S],
+    ) -> Option<&EventTransition<A, S, C>> {
+        let fn_event_eq = self.event_eq?;
+        self.events.get(&state)?.iter().find(|transition| {
+            fn_event_eq(&transition.event, action)
+                && transition.guard.is_none_or(|fn_guard| fn_guard(context, *action, active))
+        })
+    }
+}
+
+#[cfg(feature = "xstate")]
+impl<A: Copy + PartialEq, S: Eq + Hash + Copy, C: Debug + Copy> MachineDefinition<A, S, C> {
+    /// Add a transition leaving `state_name` when the machine receives `transition.event`
+    pub fn add_event_transition(&mut self, state_name: S, transition: EventTransition<A, S, C>) {
+        self.event_eq = Some(A::eq);
+        self.events.entry(state_name).or_default().push(transition);
+    }
+}
 
 /// Create and manipulate state machines
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct Machine<A, S, C> {
     /// Global state of the machine. Can be manipulated with transitions.
     pub context: C,
@@ -14,27 +106,71 @@ pub struct Machine<A, S, C> {
     /// Unique identifier for the machine. Can be reference by other machines.
     pub id: String,
 
-    /// Initial state of the machine. (readonly)
-    initial: S,
+    /// States and transitions of the machine, shared with every other instance of the same
+    /// definition. Changing them through the machine copies the definition first.
+    pub definition: Arc<MachineDefinition<A, S, C>>,
 
-    /// Available states and transitions for the machine.
-    pub states: HashMap<S, Transition<A, S, C>>,
+    /// Running orthogonal regions nested inside compound states. The regions of a state are
+    /// active while the machine is in that state.
+    pub regions: HashMap<S, ParallelMachine<A, S, C>>,
+
+    /// State the machine was in when it was last exited, resumed by its history states
+    pub history: Option<S>,
 }
 
 impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> Machine<A, S, C> {
     /// Create a new state machine
     pub fn new(id: String, initial: S, context: C) -> Self {
+        let definition = MachineDefinition::new(id.clone(), initial);
         Machine::<A, S, C> {
             context,
             value: initial,
             id,
-            initial,
-            states: HashMap::new(),
+            definition: Arc::new(definition),
+            regions: HashMap::new(),
+            history: None,
+        }
+    }
+
+    /// Spawn an instance of a shared definition at its initial state. The states and
+    /// transitions are shared, and nested regions are built from their own shared definitions.
+    pub fn from_definition(definition: Arc<MachineDefinition<A, S, C>>, context: C) -> Self {
+        let regions = definition
+            .regions
+            .iter()
+            .map(|(state, regions)| (*state, ParallelMachine::from_definition(regions)))
+            .collect();
+        Machine::<A, S, C> {
+            context,
+            value: definition.initial,
+            id: definition.id.clone(),
+            regions,
+            history: None,
+            definition,
         }
     }
 
     pub fn add_state(&mut self, state_name: S, state: Transition<A, S, C>) {
-        self.states.insert(state_name, state);
+        Arc::make_mut(&mut self.definition).add_state(state_name, state);
+    }
+
+    /// Add a compound state whose children are a set of parallel regions. The regions are
+    /// entered when the machine enters the state and exited when it leaves.
+    pub fn add_parallel_state(
+        &mut self,
+        state_name: S,
+        state: Transition<A, S, C>,
+        regions: ParallelMachine<A, S, C>,
+    ) {
+        let definition = Arc::make_mut(&mut self.definition);
+        definition.add_parallel_state(state_name, state, regions.definition());
+        self.regions.insert(state_name, regions);
+    }
+
+    /// Add a history pseudo-state. Entering it resumes the state the machine was in when it
+    /// was last exited, or `history.target` if it has never been exited.
+    pub fn add_history_state(&mut self, state_name: S, history: HistoryState<S>) {
+        Arc::make_mut(&mut self.definition).add_history_state(state_name, history);
     }
 
     pub fn set_state(&mut self, state_name: S) {
@@ -46,54 +182,368 @@ impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> Machine<A, S, C> {
         self.context = context;
     }
 
-    /// Export the current machine to SCXML format
-    pub fn to_scxml(&self) -> {
-    }
-
     /// Send an action to the state machine
     pub fn transition(&mut self, action: &A) {
-        let current_value = self.value.clone();
+        let active = self.configuration();
+        self.transition_in(action, &active, &mut HookLog::new(false));
+    }
+
+    /// Every active state, including the states of active nested regions
+    pub fn configuration(&self) -> Vec<S> {
+        let mut active = vec![self.value];
+        if let Some(regions) = self.regions.get(&self.value) {
+            active.extend(regions.configuration());
+        }
+        active
+    }
+
+    /// Send an action to the state machine, evaluating guards against the `active` states and
+    /// recording the hooks that run in `log`
+    pub(crate) fn transition_in(&mut self, action: &A, active: &[S], log: &mut HookLog<S>) {
+        let current_value = self.value;
+        let mut regions_done = false;
 
-        if let Some(transition) = self.states.get(&self.value) {
-            match transition.context {
-                Some(fn_context) => {
+        // Regions nested in the active state see the action before their parent
+        if let Some(regions) = self.regions.get_mut(&self.value) {
+            let was_done = regions.is_done();
+            regions.transition_in(action, active, log);
+            regions_done = !was_done && regions.is_done();
+        }
+
+        if let Some(transition) = self.definition.states.get(&self.value) {
+            let enabled = match transition.guard {
+                Some(fn_guard) => fn_guard(self.context, action.to_owned(), active),
+                None => true,
+            };
+
+            if enabled {
+                if let Some(fn_context) = transition.context {
+                    log.record(&self.id, HookKind::Context, self.value);
                     self.context = fn_context(self.context, action.to_owned(), self.value);
                 }
-                None => {}
-            }
 
-            match transition.on {
-                Some(fn_on) => {
+                if let Some(fn_on) = transition.on {
                     self.value = fn_on(self.context, action.to_owned(), self.value);
                 }
-                None => {}
+
+                #[cfg(feature = "xstate")]
+                if transition.on.is_none() {
+                    self.take_event_transition(action, active, log);
+                }
             }
         }
 
-        if &self.value != &current_value {
-            // Run the on_entry for the newest state
-            if let Some(transition) = self.states.get(&self.value) {
-                match transition.on_entry {
-                    Some(fn_on_entry) => {
-                        self.context = fn_on_entry(self.context, action.to_owned(), self.value);
-                    }
-                    None => {}
-                }
+        if self.value != current_value {
+            self.change_state(current_value, action, log);
+        } else if regions_done {
+            self.done(action, log);
+        }
+    }
+
+    /// Current state and context of the machine and of every nested region
+    pub fn save_state(&self) -> MachineState<S, C> {
+        MachineState {
+            id: self.id.clone(),
+            value: self.value,
+            context: self.context,
+            history: self.history,
+            regions: self
+                .regions
+                .iter()
+                .map(|(state, regions)| (*state, regions.save_state()))
+                .collect(),
+        }
+    }
+
+    /// Return to a state and context taken by `save_state`, without running any hooks. The id
+    /// of the machine is kept.
+    pub fn restore_state(&mut self, state: MachineState<S, C>) {
+        self.value = state.value;
+        self.context = state.context;
+        self.history = state.history;
+        for (state, saved) in state.regions {
+            if let Some(regions) = self.regions.get_mut(&state) {
+                regions.restore_state(saved);
+            }
+        }
+    }
+
+    /// Take the first transition declared as data for the action, running its actions
+    #[cfg(feature = "xstate")]
+    fn take_event_transition(&mut self, action: &A, active: &[S], log: &mut HookLog<S>) {
+        let definition = Arc::clone(&self.definition);
+        if let Some(event) = definition.event_transition(self.context, action, self.value, active) {
+            for fn_action in event.actions.iter() {
+                log.record(&self.id, HookKind::Action, event.target);
+                self.context = fn_action(self.context, action.to_owned(), event.target);
+            }
+            self.value = event.target;
+        }
+    }
+
+    /// Whether the machine is in a state marked as `final_state`
+    pub fn is_done(&self) -> bool {
+        match self.definition.states.get(&self.value) {
+            Some(transition) => transition.final_state,
+            None => false,
+        }
+    }
+
+    /// Whether the invariants of every active state hold, including nested regions
+    pub fn is_valid(&self) -> bool {
+        let valid = match self.definition.states.get(&self.value) {
+            Some(Transition { invariant: Some(fn_invariant), .. }) => {
+                fn_invariant(self.context, self.value)
+            }
+            _ => true,
+        };
+
+        valid
+            && match self.regions.get(&self.value) {
+                Some(regions) => regions.machines.iter().all(|machine| machine.is_valid()),
+                None => true,
+            }
+    }
+
+    /// Deliver the `done.state.<id>` event of the active regions to the current state
+    fn done(&mut self, action: &A, log: &mut HookLog<S>) {
+        let current_value = self.value;
+
+        if let (Some(transition), Some(regions)) =
+            (self.definition.states.get(&self.value), self.regions.get(&self.value))
+        {
+            if let Some(fn_on_done) = transition.on_done {
+                self.value = fn_on_done(self.context, &regions.done_event(), self.value);
+            }
+        }
+
+        if self.value != current_value {
+            self.change_state(current_value, action, log);
+        }
+    }
+
+    /// Run the exit and entry hooks after moving away from `previous_value`
+    fn change_state(&mut self, previous_value: S, action: &A, log: &mut HookLog<S>) {
+        let resume = self.resolve_history();
+
+        // Leave the regions of the previous state before running any hooks
+        if let Some(regions) = self.regions.get_mut(&previous_value) {
+            regions.exit(action, log);
+        }
+
+        // Run the on_entry for the newest state
+        if let Some(transition) = self.definition.states.get(&self.value) {
+            if let Some(fn_on_entry) = transition.on_entry {
+                log.record(&self.id, HookKind::Entry, self.value);
+                self.context = fn_on_entry(self.context, action.to_owned(), self.value);
             }
+        }
+
+        // Run the on_exit for the previous state
+        if let Some(transition) = self.definition.states.get(&previous_value) {
+            if let Some(fn_on_exit) = transition.on_exit {
+                log.record(&self.id, HookKind::Exit, previous_value);
+                self.context = fn_on_exit(self.context, action.to_owned(), self.value);
+            }
+        }
+
+        self.enter_regions(action, log, resume);
+    }
 
-            // Run the on_entry for the newest state
-            if let Some(transition) = self.states.get(&current_value) {
-                match transition.on_exit {
-                    Some(fn_on_exit) => {
-                        self.context = fn_on_exit(self.context, action.to_owned(), self.value);
-                    }
-                    None => {}
+    /// Replace a history pseudo-state with the state it resumes. Returns whether the history
+    /// is deep, so nested regions resume their own last states too.
+    fn resolve_history(&mut self) -> bool {
+        match self.definition.history.get(&self.value) {
+            Some(history) => {
+                self.value = self.history.unwrap_or(history.target);
+                history.deep
+            }
+            None => false,
+        }
+    }
+
+    /// Enter the regions of the current state, raising their done event at once if every
+    /// region starts in a final state. With `resume`, regions return to the state they were
+    /// last exited from instead of starting over.
+    fn enter_regions(&mut self, action: &A, log: &mut HookLog<S>, resume: bool) {
+        let done = match self.regions.get_mut(&self.value) {
+            Some(regions) => {
+                if resume {
+                    regions.resume(action, log);
+                } else {
+                    regions.enter(action, log);
                 }
+                regions.is_done()
+            }
+            None => false,
+        };
+
+        if done {
+            self.done(action, log);
+        }
+    }
+
+    /// Enter the machine from the outside, starting over at the initial state
+    pub(crate) fn enter(&mut self, action: &A, log: &mut HookLog<S>) {
+        self.enter_at(self.definition.initial, false, action, log);
+    }
+
+    /// Enter the machine from the outside at the state it was last exited from, resuming its
+    /// nested regions as well
+    pub(crate) fn resume(&mut self, action: &A, log: &mut HookLog<S>) {
+        let state = self.history.unwrap_or(self.definition.initial);
+        self.enter_at(state, true, action, log);
+    }
+
+    fn enter_at(&mut self, state: S, resume: bool, action: &A, log: &mut HookLog<S>) {
+        self.value = state;
+        let resume = self.resolve_history() || resume;
+
+        if let Some(transition) = self.definition.states.get(&self.value) {
+            if let Some(fn_on_entry) = transition.on_entry {
+                log.record(&self.id, HookKind::Entry, self.value);
+                self.context = fn_on_entry(self.context, action.to_owned(), self.value);
+            }
+        }
+
+        self.enter_regions(action, log, resume);
+    }
+
+    /// Leave the machine from its current state, remembering it for history states
+    pub(crate) fn exit(&mut self, action: &A, log: &mut HookLog<S>) {
+        self.history = Some(self.value);
+
+        if let Some(regions) = self.regions.get_mut(&self.value) {
+            regions.exit(action, log);
+        }
+
+        if let Some(transition) = self.definition.states.get(&self.value) {
+            if let Some(fn_on_exit) = transition.on_exit {
+                log.record(&self.id, HookKind::Exit, self.value);
+                self.context = fn_on_exit(self.context, action.to_owned(), self.value);
             }
         }
     }
 }
 
+#[cfg(feature = "xstate")]
+impl<A: Copy + PartialEq, S: Eq + Hash + Copy, C: Debug + Copy> Machine<A, S, C> {
+    /// Add a transition leaving `state_name` when the machine receives `transition.event`
+    pub fn add_event_transition(&mut self, state_name: S, transition: EventTransition<A, S, C>) {
+        Arc::make_mut(&mut self.definition).add_event_transition(state_name, transition);
+    }
+}
+
+/// Condition checked before a state handles an action
+pub type Guard<A, S, C> = fn(context: C, action: A, active: &[S]) -> bool;
+
+/// States and contexts of a machine and of its nested regions, without its definition
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MachineState<S, C> {
+    /// Identifier of the machine the state was taken from
+    pub id: String,
+
+    pub value: S,
+    pub context: C,
+
+    /// State the machine was last exited from, resumed by its history states
+    pub history: Option<S>,
+
+    /// Regions nested inside compound states, keyed by their state
+    pub regions: Vec<(S, ParallelState<S, C>)>,
+}
+
+/// A pseudo-state resuming the state its machine was in when it was last exited
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct HistoryState<S> {
+    /// State entered when the machine has never been exited
+    pub target: S,
+
+    /// Whether the nested regions of the resumed state resume their own last states too,
+    /// instead of starting over
+    pub deep: bool,
+}
+
+/// Function updating the context, such as an entry or exit hook
+pub type Hook<A, S, C> = fn(context: C, action: A, state: S) -> C;
+
+/// Kind of hook run by a machine
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookKind {
+    /// The `context` function of a state handling an action
+    Context,
+
+    /// An `on_entry` hook
+    Entry,
+
+    /// An `on_exit` hook
+    Exit,
+
+    /// One of the `actions` of an `EventTransition`
+    Action,
+}
+
+/// A hook run by a machine, as reported to the observers of a `ParallelMachine`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HookEvent<S> {
+    /// Identifier of the machine running the hook
+    pub machine: String,
+
+    pub hook: HookKind,
+
+    /// State the hook belongs to
+    pub state: S,
+}
+
+/// Hooks run while handling an action, in order. Nothing is recorded when nobody observes them.
+#[derive(Debug)]
+pub(crate) struct HookLog<S> {
+    events: Option<Vec<HookEvent<S>>>,
+}
+
+impl<S> HookLog<S> {
+    pub(crate) fn new(observed: bool) -> Self {
+        HookLog { events: if observed { Some(vec![]) } else { None } }
+    }
+
+    pub(crate) fn record(&mut self, machine: &str, hook: HookKind, state: S) {
+        if let Some(events) = self.events.as_mut() {
+            events.push(HookEvent { machine: machine.to_string(), hook, state });
+        }
+    }
+
+    /// Append the hooks recorded by `log` after the ones already recorded
+    pub(crate) fn append(&mut self, log: HookLog<S>) {
+        if let (Some(events), Some(other)) = (self.events.as_mut(), log.events) {
+            events.extend(other);
+        }
+    }
+
+    pub(crate) fn into_events(self) -> Vec<HookEvent<S>> {
+        self.events.unwrap_or_default()
+    }
+}
+
+/// A transition declared as data instead of an `on` function, as in xstate configs
+#[cfg(feature = "xstate")]
+#[derive(Debug, Clone)]
+pub struct EventTransition<A, S, C> {
+    /// The action that triggers the transition
+    pub event: A,
+
+    /// The state to transition to
+    pub target: S,
+
+    /// Condition for the transition to be taken
+    pub guard: Option<Guard<A, S, C>>,
+
+    /// Functions updating the context when the transition is taken, in order
+    pub actions: Vec<Hook<A, S, C>>,
+}
+
+// Transitions compare by the addresses of their hooks, which is enough to tell them apart
+#[allow(unpredictable_function_pointer_comparisons)]
 #[derive(Debug, PartialEq, Eq, Hash)]
 pub struct Transition<A, S, C> {
     /// The state to transition to
@@ -108,9 +558,39 @@ pub struct Transition<A, S, C> {
     /// The action to execute when running this transition
     pub context: Option<fn(context: C, action: A, state: S) -> C>,
 
-    pub final_state: bool
+    /// Condition for the state to handle an action. `active` holds every active state across
+    /// parallel regions, so `active.contains(&state)` behaves like SCXML's `In()` predicate.
+    pub guard: Option<Guard<A, S, C>>,
+
+    /// Condition that must hold while the machine is in this state. Checked by atomic
+    /// transitions such as `ParallelMachine::try_transition`.
+    pub invariant: Option<fn(context: C, state: S) -> bool>,
+
+    /// The state to transition to once every nested region is done, receiving the
+    /// `done.state.<id>` event of the regions
+    pub on_done: Option<fn(context: C, event: &str, state: S) -> S>,
+
+    pub final_state: bool,
+
+    /// What the state means, shown by the diagram exporters
+    pub description: Option<&'static str>,
+
+    /// Remark attached to the state in diagrams
+    pub note: Option<&'static str>,
+
+    /// Names of the `on_entry` and `on_exit` hooks, shown by the diagram exporters
+    pub entry_name: Option<&'static str>,
+    pub exit_name: Option<&'static str>,
 }
 
+impl<A, S, C> Clone for Transition<A, S, C> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<A, S, C> Copy for Transition<A, S, C> {}
+
 impl<A, S, C> Default for Transition<A, S, C> {
     fn default() -> Self {
         Transition {
@@ -118,7 +598,14 @@ impl<A, S, C> Default for Transition<A, S, C> {
             on_entry: None,
             on_exit: None,
             context: None,
+            guard: None,
+            invariant: None,
+            on_done: None,
             final_state: false,
+            description: None,
+            note: None,
+            entry_name: None,
+            exit_name: None,
         }
     }
 }
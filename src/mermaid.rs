@@ -0,0 +1,97 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::graph::*;
+use crate::machine::*;
+use crate::parallel::*;
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> Machine<A, S, C> {
+    /// Render the graph of the machine, discovered from `actions`, as a Mermaid
+    /// `stateDiagram-v2`. Parallel regions are nested in their state and separated by `--`.
+    pub fn to_mermaid(&self, actions: &[A]) -> String {
+        let mut mermaid = "stateDiagram-v2\n".to_string();
+        write_graph(&mut mermaid, &self.graph(actions), &self.id, 1);
+        mermaid
+    }
+}
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> ParallelMachine<A, S, C> {
+    /// Render the graph of every region, discovered from `actions`, as a Mermaid
+    /// `stateDiagram-v2`. Regions are nested in a state named after the machine, since
+    /// Mermaid only separates concurrent regions inside a composite state.
+    pub fn to_mermaid(&self, actions: &[A]) -> String {
+        let id = identifier(&self.id);
+        let mut mermaid = "stateDiagram-v2\n".to_string();
+        mermaid.push_str(&format!("    state \"{}\" as {}\n", label(&self.id), id));
+        mermaid.push_str(&format!("    [*] --> {}\n", id));
+        write_regions(&mut mermaid, &self.graphs(actions), &self.id, 1);
+        mermaid
+    }
+}
+
+/// Mermaid ends labels at a `:` and uses `"` to delimit them
+fn label(text: &str) -> String {
+    text.replace('"', "#quot;").replace(':', "#58;")
+}
+
+/// Write the states and transitions of a graph, naming states after the machine at `path`
+fn write_graph<S: Debug>(mermaid: &mut String, graph: &StateGraph<S>, path: &str, depth: usize) {
+    let indent = "    ".repeat(depth);
+
+    for state in graph.states.iter() {
+        let id = state_id(path, &state.state);
+        let name = label(&format!("{:?}", state.state));
+        mermaid.push_str(&format!("{}state \"{}\" as {}\n", indent, name, id));
+    }
+
+    mermaid.push_str(&format!("{}[*] --> {}\n", indent, state_id(path, &graph.initial)));
+    for edge in graph.edges.iter() {
+        mermaid.push_str(&format!(
+            "{}{} --> {} : {}\n",
+            indent,
+            state_id(path, &edge.from),
+            state_id(path, &edge.to),
+            label(&edge.events.join(", "))
+        ));
+    }
+
+    for state in graph.states.iter() {
+        if state.final_state {
+            mermaid.push_str(&format!("{}{} --> [*]\n", indent, state_id(path, &state.state)));
+        }
+    }
+
+    for state in graph.states.iter().filter(|state| !state.regions.is_empty()) {
+        let state_path = format!("{}.{:?}", path, state.state);
+        write_regions(mermaid, &state.regions, &state_path, depth);
+    }
+}
+
+/// Write the regions of the composite state at `path`, separated by `--`. Each region is a
+/// composite state of its own, so it keeps its id as a label.
+fn write_regions<S: Debug>(
+    mermaid: &mut String,
+    regions: &[StateGraph<S>],
+    path: &str,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+    mermaid.push_str(&format!("{}state {} {{\n", indent, identifier(path)));
+
+    for (index, region) in regions.iter().enumerate() {
+        if index > 0 {
+            mermaid.push_str(&format!("{}    --\n", indent));
+        }
+
+        let region_path = format!("{}.{}", path, region.id);
+        let region_id = identifier(&region_path);
+        let name = label(&region.id);
+        mermaid.push_str(&format!("{}    state \"{}\" as {}\n", indent, name, region_id));
+        mermaid.push_str(&format!("{}    [*] --> {}\n", indent, region_id));
+        mermaid.push_str(&format!("{}    state {} {{\n", indent, region_id));
+        write_graph(mermaid, region, &region_path, depth + 2);
+        mermaid.push_str(&format!("{}    }}\n", indent));
+    }
+
+    mermaid.push_str(&format!("{}}}\n", indent));
+}
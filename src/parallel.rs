@@ -1,27 +1,387 @@
+use std::collections::HashMap;
 use std::hash::Hash;
+use std::fmt;
 use std::fmt::Debug;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::sync::mpsc;
+use std::sync::Arc;
 
+use crate::error::*;
 use crate::machine::*;
+use crate::pool::*;
 
-#[derive(Debug)]
+/// States and contexts of the regions of a parallel machine, in order
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ParallelState<S, C> {
+    /// Identifier of the machine the states were taken from
+    pub id: String,
+
+    pub machines: Vec<MachineState<S, C>>,
+
+    /// Context shared by every region
+    pub context: Option<C>,
+}
+
+/// Definition of a region, from which every instance of a compound state builds its own
+#[derive(Debug, Clone)]
+pub struct RegionDefinition<A, S, C> {
+    /// Identifier of the region instances
+    pub id: String,
+
+    pub definition: Arc<MachineDefinition<A, S, C>>,
+
+    /// Context of the region instances when they are built
+    pub context: C,
+}
+
+/// Definition of the parallel regions nested in a compound state, shared by every instance of
+/// the machine holding them
+#[derive(Debug, Clone)]
+pub struct ParallelDefinition<A, S, C> {
+    pub id: String,
+    pub regions: Vec<RegionDefinition<A, S, C>>,
+
+    /// Context shared by every region, if any
+    pub context: Option<C>,
+}
+
+/// Function notified of every hook run by the regions of a parallel machine
+pub type Observer<S> = Arc<dyn Fn(&HookEvent<S>) + Send + Sync>;
+
+#[derive(Clone)]
 pub struct ParallelMachine<A, S, C> {
     pub id: String,
     pub machines: Vec<Machine<A, S, C>>,
-    pub value: Vec<S>
+    pub value: Vec<S>,
+
+    /// Context shared by every region. When `None`, each region keeps its own context.
+    pub context: Option<C>,
+
+    /// Notified of the hooks run by the regions, in region order, once an action is handled
+    observers: Vec<Observer<S>>,
+}
+
+impl<A: Debug, S: Debug, C: Debug> Debug for ParallelMachine<A, S, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ParallelMachine")
+            .field("id", &self.id)
+            .field("machines", &self.machines)
+            .field("value", &self.value)
+            .field("context", &self.context)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
 }
 
 impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> ParallelMachine<A, S, C> {
     /// Create a new state machine
     pub fn new(id: String, machines: Vec<Machine<A, S, C>>) -> Self {
         let value = machines.iter().map(|machine| machine.value).collect();
-        ParallelMachine { id, machines, value }
+        ParallelMachine { id, machines, value, context: None, observers: vec![] }
+    }
+
+    /// Build the regions of a definition, each one at its initial state
+    pub fn from_definition(definition: &ParallelDefinition<A, S, C>) -> Self {
+        let machines = definition
+            .regions
+            .iter()
+            .map(|region| {
+                let mut machine =
+                    Machine::from_definition(Arc::clone(&region.definition), region.context);
+                machine.id = region.id.clone();
+                machine
+            })
+            .collect();
+
+        match definition.context {
+            Some(context) => {
+                ParallelMachine::with_context(definition.id.clone(), machines, context)
+            }
+            None => ParallelMachine::new(definition.id.clone(), machines),
+        }
+    }
+
+    /// Definition of the regions, sharing the definition of each region machine
+    pub fn definition(&self) -> ParallelDefinition<A, S, C> {
+        let regions = self
+            .machines
+            .iter()
+            .map(|machine| RegionDefinition {
+                id: machine.id.clone(),
+                definition: Arc::clone(&machine.definition),
+                context: machine.context,
+            })
+            .collect();
+        ParallelDefinition { id: self.id.clone(), regions, context: self.context }
+    }
+
+    /// Create a new state machine whose regions read and update a single shared context.
+    /// Regions see the action in the order they were given, each one receiving the context
+    /// left by the previous region.
+    pub fn with_context(id: String, machines: Vec<Machine<A, S, C>>, context: C) -> Self {
+        let mut machine = ParallelMachine::new(id, machines);
+        machine.context = Some(context);
+        machine.run(|_machine| {});
+        machine
+    }
+
+    /// Notify `observer` of every hook run by the regions. Hooks are reported once the machine
+    /// has handled an action, in the order sequential evaluation runs them.
+    pub fn subscribe(&mut self, observer: impl Fn(&HookEvent<S>) + Send + Sync + 'static) {
+        self.observers.push(Arc::new(observer));
     }
 
     /// Send an action to the state machines
     pub fn transition(&mut self, action: &A) {
-        for machine in self.machines.iter_mut() {
-            machine.transition(action);
+        let active = self.configuration();
+        let mut log = self.log();
+        self.transition_in(action, &active, &mut log);
+        self.notify(log);
+    }
+
+    /// Send an action to the state machines atomically. If a hook panics in any region, or a
+    /// region ends up in a state whose invariant does not hold, every region is rolled back to
+    /// its state and context from before the action and an error is returned. Observers are
+    /// only notified of the hooks of actions that are not rolled back.
+    pub fn try_transition(&mut self, action: &A) -> Result<(), Error> {
+        let saved = self.save_state();
+        let active = self.configuration();
+        let mut log = self.log();
+
+        let mut result = Ok(());
+        for index in 0..self.machines.len() {
+            let step = panic::catch_unwind(AssertUnwindSafe(|| {
+                self.step(index, |machine| machine.transition_in(action, &active, &mut log));
+            }));
+
+            let machine = &self.machines[index];
+            if step.is_err() {
+                result = Err(Error::Panicked(machine.id.clone()));
+                break;
+            }
+        }
+
+        if result.is_ok() {
+            if let Some(machine) = self.machines.iter().find(|machine| !machine.is_valid()) {
+                result = Err(Error::InvariantViolated(machine.id.clone()));
+            }
+        }
+
+        match result {
+            Ok(()) => {
+                self.sync();
+                self.notify(log);
+            }
+            Err(_) => {
+                self.restore_state(saved);
+                self.sync();
+            }
+        }
+        result
+    }
+
+    /// Every active state across all regions
+    pub fn configuration(&self) -> Vec<S> {
+        self.machines.iter().flat_map(|machine| machine.configuration()).collect()
+    }
+
+    /// Send an action to the state machines, evaluating guards against the `active` states.
+    /// Every region sees the configuration from before the action.
+    pub(crate) fn transition_in(&mut self, action: &A, active: &[S], log: &mut HookLog<S>) {
+        self.run(|machine| machine.transition_in(action, active, log));
+    }
+
+    /// Region with the given `id`
+    pub fn region(&self, id: &str) -> Option<&Machine<A, S, C>> {
+        self.machines.iter().find(|machine| machine.id == id)
+    }
+
+    /// Mutable region with the given `id`
+    pub fn region_mut(&mut self, id: &str) -> Option<&mut Machine<A, S, C>> {
+        self.machines.iter_mut().find(|machine| machine.id == id)
+    }
+
+    /// Current state of every region, keyed by region id
+    pub fn value_map(&self) -> HashMap<String, S> {
+        self.machines.iter().map(|machine| (machine.id.clone(), machine.value)).collect()
+    }
+
+    /// Add a region at runtime. The region is entered at its initial state, running its
+    /// `on_entry` hook with `action`. Fails if a region with the same id already exists.
+    pub fn add_region(&mut self, machine: Machine<A, S, C>, action: &A) -> Result<(), Error> {
+        if self.region(&machine.id).is_some() {
+            return Err(Error::DuplicateRegion(machine.id));
+        }
+
+        let mut log = self.log();
+        self.machines.push(machine);
+        self.step(self.machines.len() - 1, |machine| machine.enter(action, &mut log));
+        self.sync();
+        self.notify(log);
+        Ok(())
+    }
+
+    /// Remove the region with the given `id` at runtime. The region is exited first, running
+    /// its `on_exit` hook with `action`.
+    pub fn remove_region(&mut self, id: &str, action: &A) -> Option<Machine<A, S, C>> {
+        let index = self.machines.iter().position(|machine| machine.id == id)?;
+        let mut log = self.log();
+        self.step(index, |machine| machine.exit(action, &mut log));
+        let machine = self.machines.remove(index);
+        self.sync();
+        self.notify(log);
+        Some(machine)
+    }
+
+    /// Current state and context of every region, including nested regions
+    pub fn save_state(&self) -> ParallelState<S, C> {
+        ParallelState {
+            id: self.id.clone(),
+            machines: self.machines.iter().map(|machine| machine.save_state()).collect(),
+            context: self.context,
+        }
+    }
+
+    /// Return to the states and contexts taken by `save_state`, without running any hooks
+    pub fn restore_state(&mut self, state: ParallelState<S, C>) {
+        for (machine, saved) in self.machines.iter_mut().zip(state.machines) {
+            machine.restore_state(saved);
+        }
+        self.context = state.context;
+        self.sync();
+    }
+
+    /// Whether every region is in a state marked as `final_state`. A machine without regions
+    /// is never done.
+    pub fn is_done(&self) -> bool {
+        !self.machines.is_empty() && self.machines.iter().all(|machine| machine.is_done())
+    }
+
+    /// Name of the event raised once every region is done
+    pub fn done_event(&self) -> String {
+        format!("done.state.{}", self.id)
+    }
+
+    /// Enter every region at its initial state
+    pub(crate) fn enter(&mut self, action: &A, log: &mut HookLog<S>) {
+        self.run(|machine| machine.enter(action, log));
+    }
+
+    /// Enter every region at the state it was last exited from
+    pub(crate) fn resume(&mut self, action: &A, log: &mut HookLog<S>) {
+        self.run(|machine| machine.resume(action, log));
+    }
+
+    /// Exit every region from its current state
+    pub(crate) fn exit(&mut self, action: &A, log: &mut HookLog<S>) {
+        self.run(|machine| machine.exit(action, log));
+    }
+
+    /// Log for the hooks of a single action, recording them only if someone observes them
+    fn log(&self) -> HookLog<S> {
+        HookLog::new(!self.observers.is_empty())
+    }
+
+    /// Report the hooks recorded in `log` to every observer
+    fn notify(&self, log: HookLog<S>) {
+        for event in log.into_events().iter() {
+            for observer in self.observers.iter() {
+                observer(event);
+            }
+        }
+    }
+
+    /// Run `step` on every region in order, threading the shared context through them
+    fn run<F: FnMut(&mut Machine<A, S, C>)>(&mut self, mut step: F) {
+        for index in 0..self.machines.len() {
+            self.step(index, &mut step);
+        }
+        self.sync();
+    }
+
+    /// Run `step` on a single region with the shared context
+    fn step<F: FnMut(&mut Machine<A, S, C>)>(&mut self, index: usize, mut step: F) {
+        let machine = &mut self.machines[index];
+        if let Some(context) = self.context {
+            machine.context = context;
+        }
+        step(machine);
+        if self.context.is_some() {
+            self.context = Some(machine.context);
+        }
+    }
+
+    /// Hand the shared context back to every region and refresh the value
+    fn sync(&mut self) {
+        if let Some(context) = self.context {
+            for machine in self.machines.iter_mut() {
+                machine.context = context;
+            }
         }
         self.value = self.machines.iter().map(|machine| machine.value).collect();
     }
 }
+
+impl<A, S, C> ParallelMachine<A, S, C>
+where
+    A: Copy + Send + Sync + 'static,
+    S: Eq + Hash + Copy + Send + Sync + 'static,
+    C: Debug + Copy + Send + Sync + 'static,
+{
+    /// Send an action to the state machines, evaluating the regions concurrently on the
+    /// workers of `pool`. Regions are split into contiguous batches and every region sees the
+    /// configuration from before the action. Hooks are buffered per batch and reported in
+    /// region order, so the resulting `value`, contexts and observed hooks match `transition`.
+    /// Regions with a shared context are always evaluated in order.
+    pub fn transition_concurrent(&mut self, action: &A, pool: &WorkerPool) {
+        if pool.size() <= 1 || self.context.is_some() || self.machines.is_empty() {
+            return self.transition(action);
+        }
+
+        let active = Arc::new(self.configuration());
+        let observed = !self.observers.is_empty();
+        let batch_size = self.machines.len().div_ceil(pool.size());
+        let (sender, receiver) = mpsc::channel();
+
+        let mut machines = std::mem::take(&mut self.machines);
+        let mut batches = 0;
+        while !machines.is_empty() {
+            let rest = machines.split_off(batch_size.min(machines.len()));
+            let mut batch = std::mem::replace(&mut machines, rest);
+            let (action, active, sender) = (*action, Arc::clone(&active), sender.clone());
+            let index = batches;
+
+            pool.execute(move || {
+                let mut log = HookLog::new(observed);
+                // Hand the batch back even if a hook panics, so no region is lost
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    for machine in batch.iter_mut() {
+                        machine.transition_in(&action, &active, &mut log);
+                    }
+                }));
+                let _ = sender.send((index, batch, log, result.err()));
+            });
+            batches += 1;
+        }
+        drop(sender);
+
+        let mut results: Vec<_> = receiver.iter().collect();
+        results.sort_by_key(|(index, ..)| *index);
+
+        let mut log = self.log();
+        let mut panicked = None;
+        for (_, batch, batch_log, result) in results {
+            self.machines.extend(batch);
+            log.append(batch_log);
+            panicked = panicked.or(result);
+        }
+        self.sync();
+
+        if let Some(payload) = panicked {
+            panic::resume_unwind(payload);
+        }
+        self.notify(log);
+    }
+}
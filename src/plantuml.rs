@@ -0,0 +1,130 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::graph::*;
+use crate::machine::*;
+use crate::parallel::*;
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> Machine<A, S, C> {
+    /// Render the graph of the machine, discovered from `actions`, as a PlantUML state
+    /// diagram. States show their description, note and the names of their entry and exit
+    /// hooks, and parallel regions are nested in their state and separated by `--`.
+    pub fn to_plantuml(&self, actions: &[A]) -> String {
+        let mut plantuml = "@startuml\n".to_string();
+        write_graph(&mut plantuml, &self.graph(actions), &self.id, 0);
+        plantuml.push_str("@enduml\n");
+        plantuml
+    }
+}
+
+impl<A: Copy + Debug, S: Eq + Hash + Copy + Debug, C: Debug + Copy> ParallelMachine<A, S, C> {
+    /// Render the graph of every region, discovered from `actions`, as a PlantUML state
+    /// diagram, with the regions nested in a state named after the machine
+    pub fn to_plantuml(&self, actions: &[A]) -> String {
+        let mut plantuml = "@startuml\n".to_string();
+        let id = identifier(&self.id);
+        plantuml.push_str(&format!("[*] --> {}\n", id));
+        plantuml.push_str(&format!("state \"{}\" as {} {{\n", name(&self.id), id));
+        write_regions(&mut plantuml, &self.graphs(actions), &self.id, 1);
+        plantuml.push_str("}\n@enduml\n");
+        plantuml
+    }
+}
+
+/// PlantUML display names are delimited by `"` and cannot escape it
+fn name(text: &str) -> String {
+    text.replace('"', "'")
+}
+
+/// PlantUML ends descriptions and edge labels at a newline, and splits them at a `:`
+fn escape(text: &str) -> String {
+    text.replace('\n', "\\n").replace(':', "<U+003A>")
+}
+
+/// Line describing an entry or exit hook, with its name when it has one
+fn hook(kind: &str, hook_name: Option<&str>) -> String {
+    match hook_name {
+        Some(hook_name) => format!("{} / {}", kind, escape(hook_name)),
+        None => kind.to_string(),
+    }
+}
+
+/// Write the initial state, the states with their details and the transitions of a graph
+fn write_graph<S: Debug>(plantuml: &mut String, graph: &StateGraph<S>, path: &str, depth: usize) {
+    let indent = "    ".repeat(depth);
+    plantuml.push_str(&format!("{}[*] --> {}\n", indent, state_id(path, &graph.initial)));
+
+    for state in graph.states.iter() {
+        let id = state_id(path, &state.state);
+        let label = name(&format!("{:?}", state.state));
+
+        if state.regions.is_empty() {
+            plantuml.push_str(&format!("{}state \"{}\" as {}\n", indent, label, id));
+        } else {
+            let state_path = format!("{}.{:?}", path, state.state);
+            plantuml.push_str(&format!("{}state \"{}\" as {} {{\n", indent, label, id));
+            write_regions(plantuml, &state.regions, &state_path, depth + 1);
+            plantuml.push_str(&format!("{}}}\n", indent));
+        }
+
+        if let Some(description) = state.description {
+            plantuml.push_str(&format!("{}{} : {}\n", indent, id, escape(description)));
+        }
+        if state.entry {
+            plantuml.push_str(&format!("{}{} : {}\n", indent, id, hook("entry", state.entry_name)));
+        }
+        if state.exit {
+            plantuml.push_str(&format!("{}{} : {}\n", indent, id, hook("exit", state.exit_name)));
+        }
+        if let Some(note) = state.note {
+            plantuml.push_str(&format!("{}note right of {}\n", indent, id));
+            for line in note.lines() {
+                plantuml.push_str(&format!("{}    {}\n", indent, line));
+            }
+            plantuml.push_str(&format!("{}end note\n", indent));
+        }
+    }
+
+    for edge in graph.edges.iter() {
+        plantuml.push_str(&format!(
+            "{}{} --> {} : {}\n",
+            indent,
+            state_id(path, &edge.from),
+            state_id(path, &edge.to),
+            escape(&edge.events.join(", "))
+        ));
+    }
+
+    for state in graph.states.iter().filter(|state| state.final_state) {
+        plantuml.push_str(&format!("{}{} --> [*]\n", indent, state_id(path, &state.state)));
+    }
+}
+
+/// Write every region as a state displayed with its id and entered on its own, with `--`
+/// between regions
+fn write_regions<S: Debug>(
+    plantuml: &mut String,
+    regions: &[StateGraph<S>],
+    path: &str,
+    depth: usize,
+) {
+    let indent = "    ".repeat(depth);
+
+    for (index, region) in regions.iter().enumerate() {
+        if index > 0 {
+            plantuml.push_str(&format!("{}--\n", indent));
+        }
+
+        let region_path = format!("{}.{}", path, region.id);
+        let region_id = identifier(&region_path);
+        plantuml.push_str(&format!("{}[*] --> {}\n", indent, region_id));
+        plantuml.push_str(&format!(
+            "{}state \"{}\" as {} {{\n",
+            indent,
+            name(&region.id),
+            region_id
+        ));
+        write_graph(plantuml, region, &region_path, depth + 1);
+        plantuml.push_str(&format!("{}}}\n", indent));
+    }
+}
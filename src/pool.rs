@@ -0,0 +1,58 @@
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+/// A fixed set of worker threads, kept alive to evaluate parallel regions concurrently
+#[derive(Debug)]
+pub struct WorkerPool {
+    sender: Option<mpsc::Sender<Job>>,
+    workers: Vec<thread::JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    /// Start a pool of `threads` workers, at least one
+    pub fn new(threads: usize) -> Self {
+        let (sender, receiver) = mpsc::channel::<Job>();
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        let workers = (0..threads.max(1))
+            .map(|_| {
+                let receiver = Arc::clone(&receiver);
+                thread::spawn(move || loop {
+                    // Release the lock before running the job, so other workers can take one
+                    let job = receiver.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => job(),
+                        Err(_) => break,
+                    }
+                })
+            })
+            .collect();
+
+        WorkerPool { sender: Some(sender), workers }
+    }
+
+    /// Number of worker threads
+    pub fn size(&self) -> usize {
+        self.workers.len()
+    }
+
+    /// Run `job` on the first idle worker
+    pub(crate) fn execute<F: FnOnce() + Send + 'static>(&self, job: F) {
+        if let Some(sender) = self.sender.as_ref() {
+            sender.send(Box::new(job)).expect("worker pool has stopped");
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    /// Let the workers finish their jobs and wait for them to stop
+    fn drop(&mut self) {
+        self.sender.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
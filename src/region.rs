@@ -0,0 +1,145 @@
+use std::any::Any;
+use std::fmt;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::machine::*;
+use crate::parallel::*;
+
+/// A region of a parallel state machine. Regions only need to agree on the action type, so
+/// each one can have its own state and context types.
+pub trait Region<A> {
+    /// Unique identifier of the region
+    fn id(&self) -> &str;
+
+    /// Send an action to the region
+    fn transition(&mut self, action: &A);
+
+    /// Whether the region is in a final state
+    fn is_done(&self) -> bool;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<A, S, C> Region<A> for Machine<A, S, C>
+where
+    A: Copy + 'static,
+    S: Eq + Hash + Copy + 'static,
+    C: Debug + Copy + 'static,
+{
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn transition(&mut self, action: &A) {
+        Machine::transition(self, action);
+    }
+
+    fn is_done(&self) -> bool {
+        Machine::is_done(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+impl<A, S, C> Region<A> for ParallelMachine<A, S, C>
+where
+    A: Copy + 'static,
+    S: Eq + Hash + Copy + 'static,
+    C: Debug + Copy + 'static,
+{
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn transition(&mut self, action: &A) {
+        ParallelMachine::transition(self, action);
+    }
+
+    fn is_done(&self) -> bool {
+        ParallelMachine::is_done(self)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+/// Parallel state machine whose regions have independent state and context types
+pub struct DynParallelMachine<A> {
+    pub id: String,
+    pub regions: Vec<Box<dyn Region<A>>>,
+}
+
+impl<A> DynParallelMachine<A> {
+    /// Create a new state machine
+    pub fn new(id: String, regions: Vec<Box<dyn Region<A>>>) -> Self {
+        DynParallelMachine { id, regions }
+    }
+
+    /// Send an action to the state machines
+    pub fn transition(&mut self, action: &A) {
+        for region in self.regions.iter_mut() {
+            region.transition(action);
+        }
+    }
+
+    /// Whether every region is in a final state. A machine without regions is never done.
+    pub fn is_done(&self) -> bool {
+        !self.regions.is_empty() && self.regions.iter().all(|region| region.is_done())
+    }
+
+    /// Name of the event raised once every region is done
+    pub fn done_event(&self) -> String {
+        format!("done.state.{}", self.id)
+    }
+
+    /// Region at `index`, if it is a `Machine<A, S, C>`
+    pub fn machine<S: 'static, C: 'static>(&self, index: usize) -> Option<&Machine<A, S, C>>
+    where
+        A: 'static,
+    {
+        self.regions.get(index)?.as_any().downcast_ref()
+    }
+
+    /// Mutable region at `index`, if it is a `Machine<A, S, C>`
+    pub fn machine_mut<S: 'static, C: 'static>(
+        &mut self,
+        index: usize,
+    ) -> Option<&mut Machine<A, S, C>>
+    where
+        A: 'static,
+    {
+        self.regions.get_mut(index)?.as_any_mut().downcast_mut()
+    }
+
+    /// Current state of the region at `index`, if it is a `Machine<A, S, C>`
+    pub fn value<S: Copy + 'static, C: 'static>(&self, index: usize) -> Option<S>
+    where
+        A: 'static,
+    {
+        self.machine::<S, C>(index).map(|machine| machine.value)
+    }
+}
+
+impl<A> Debug for DynParallelMachine<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let regions: Vec<&str> = self.regions.iter().map(|region| region.id()).collect();
+        f.debug_struct("DynParallelMachine")
+            .field("id", &self.id)
+            .field("regions", &regions)
+            .finish()
+    }
+}
@@ -0,0 +1,238 @@
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::*;
+use crate::history::*;
+use crate::machine::*;
+use crate::parallel::*;
+use crate::state_machine::*;
+
+/// Save and restore the running state of a machine. Hooks are fn pointers and cannot be
+/// serialized, so a snapshot only holds states and contexts and is restored into a machine
+/// that already has its definition.
+pub trait Persist {
+    /// Serializable running state of the machine
+    type Snapshot: Serialize + DeserializeOwned;
+
+    /// Identifier of the machine, stored alongside versioned snapshots
+    fn id(&self) -> &str;
+
+    /// Capture the running state of the machine
+    fn to_snapshot(&self) -> Self::Snapshot;
+
+    /// Replace the running state of the machine with `snapshot`. The machine is left untouched
+    /// when the snapshot does not match its definition.
+    fn restore_from(&mut self, snapshot: Self::Snapshot) -> Result<(), Error>;
+}
+
+/// Running state of a `HistoryMachine`, including the steps to undo and redo
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistorySnapshot<A, T> {
+    /// Snapshot of the wrapped machine
+    pub machine: T,
+
+    pub past: Vec<HistoryEntry<A, T>>,
+    pub future: Vec<HistoryEntry<A, T>>,
+}
+
+/// A snapshot tagged with the schema version it was written with and the id of its machine
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VersionedSnapshot<T> {
+    pub version: u32,
+    pub id: String,
+    pub snapshot: T,
+}
+
+/// Current schema version of the snapshots of a machine, with migrations upgrading snapshots
+/// written with older versions. Migrations work on a self-describing value `T`, such as
+/// `serde_json::Value`, since old snapshots may no longer deserialize into the current types.
+#[derive(Debug, Clone)]
+pub struct SnapshotSchema<T> {
+    pub version: u32,
+    migrations: HashMap<u32, fn(snapshot: T) -> T>,
+}
+
+impl<T> SnapshotSchema<T> {
+    /// Create a schema at `version` without any migrations
+    pub fn new(version: u32) -> Self {
+        SnapshotSchema { version, migrations: HashMap::new() }
+    }
+
+    /// Register the migration upgrading snapshots from version `from` to `from + 1`
+    pub fn add_migration(&mut self, from: u32, migration: fn(snapshot: T) -> T) {
+        self.migrations.insert(from, migration);
+    }
+
+    /// Snapshot of `machine` tagged with the current version
+    pub fn save<P: Persist>(&self, machine: &P) -> VersionedSnapshot<P::Snapshot> {
+        VersionedSnapshot {
+            version: self.version,
+            id: machine.id().to_string(),
+            snapshot: machine.to_snapshot(),
+        }
+    }
+
+    /// Upgrade a snapshot to the current version, one version step at a time. Snapshots written
+    /// with a newer version are rejected.
+    pub fn migrate(&self, versioned: VersionedSnapshot<T>) -> Result<VersionedSnapshot<T>, Error> {
+        let VersionedSnapshot { mut version, id, mut snapshot } = versioned;
+        if version > self.version {
+            return Err(Error::UnsupportedVersion(version));
+        }
+
+        while version < self.version {
+            match self.migrations.get(&version) {
+                Some(fn_migration) => snapshot = fn_migration(snapshot),
+                None => return Err(Error::MigrationNotFound(version)),
+            }
+            version += 1;
+        }
+
+        Ok(VersionedSnapshot { version, id, snapshot })
+    }
+
+    /// Upgrade a snapshot to the current version and restore it into `machine`
+    pub fn load<'de, P: Persist>(
+        &self,
+        machine: &mut P,
+        versioned: VersionedSnapshot<T>,
+    ) -> Result<(), Error>
+    where
+        T: Deserializer<'de>,
+    {
+        let versioned = self.migrate(versioned)?;
+        if versioned.id != machine.id() {
+            return Err(Error::SnapshotMismatch(machine.id().to_string()));
+        }
+
+        let snapshot = P::Snapshot::deserialize(versioned.snapshot)
+            .map_err(|error| Error::InvalidSnapshot(error.to_string()))?;
+        machine.restore_from(snapshot)
+    }
+}
+
+impl<A, S, C> Machine<A, S, C>
+where
+    A: Copy,
+    S: Eq + Hash + Copy + Serialize + DeserializeOwned,
+    C: Debug + Copy + Serialize + DeserializeOwned,
+{
+    /// Spawn an instance of a shared definition from a snapshot, taking the id of the snapshot
+    pub fn from_snapshot(
+        definition: Arc<MachineDefinition<A, S, C>>,
+        snapshot: MachineState<S, C>,
+    ) -> Result<Self, Error> {
+        let mut machine = Machine::from_definition(definition, snapshot.context);
+        machine.id = snapshot.id.clone();
+        machine.restore_from(snapshot)?;
+        Ok(machine)
+    }
+}
+
+impl<A, S, C> Persist for Machine<A, S, C>
+where
+    A: Copy,
+    S: Eq + Hash + Copy + Serialize + DeserializeOwned,
+    C: Debug + Copy + Serialize + DeserializeOwned,
+{
+    type Snapshot = MachineState<S, C>;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn to_snapshot(&self) -> MachineState<S, C> {
+        self.save_state()
+    }
+
+    fn restore_from(&mut self, snapshot: MachineState<S, C>) -> Result<(), Error> {
+        let states = &self.definition.states;
+        let unknown = !states.is_empty() && !states.contains_key(&snapshot.value);
+        if snapshot.id != self.id || unknown {
+            return Err(Error::SnapshotMismatch(self.id.clone()));
+        }
+
+        let mut regions = self.regions.clone();
+        for (state, region_snapshot) in snapshot.regions {
+            match regions.get_mut(&state) {
+                Some(region) => region.restore_from(region_snapshot)?,
+                None => return Err(Error::SnapshotMismatch(self.id.clone())),
+            }
+        }
+
+        self.value = snapshot.value;
+        self.context = snapshot.context;
+        self.history = snapshot.history;
+        self.regions = regions;
+        Ok(())
+    }
+}
+
+impl<A, S, C> Persist for ParallelMachine<A, S, C>
+where
+    A: Copy,
+    S: Eq + Hash + Copy + Serialize + DeserializeOwned,
+    C: Debug + Copy + Serialize + DeserializeOwned,
+{
+    type Snapshot = ParallelState<S, C>;
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn to_snapshot(&self) -> ParallelState<S, C> {
+        self.save_state()
+    }
+
+    fn restore_from(&mut self, snapshot: ParallelState<S, C>) -> Result<(), Error> {
+        if snapshot.id != self.id || snapshot.machines.len() != self.machines.len() {
+            return Err(Error::SnapshotMismatch(self.id.clone()));
+        }
+
+        let mut machines = self.machines.clone();
+        for (machine, region_snapshot) in machines.iter_mut().zip(snapshot.machines) {
+            machine.restore_from(region_snapshot)?;
+        }
+
+        self.value = machines.iter().map(|machine| machine.value).collect();
+        self.machines = machines;
+        self.context = snapshot.context;
+        Ok(())
+    }
+}
+
+impl<A, S, C, M> Persist for HistoryMachine<A, S, C, M>
+where
+    A: Copy + Serialize + DeserializeOwned,
+    S: Clone + PartialEq + Serialize + DeserializeOwned,
+    C: Clone + Serialize + DeserializeOwned,
+    M: StateMachine<A, Value = S, Context = C>
+        + Persist<Snapshot = <M as StateMachine<A>>::Snapshot>,
+    <M as StateMachine<A>>::Snapshot: Serialize + DeserializeOwned,
+{
+    type Snapshot = HistorySnapshot<A, <M as Persist>::Snapshot>;
+
+    fn id(&self) -> &str {
+        self.machine.id()
+    }
+
+    fn to_snapshot(&self) -> Self::Snapshot {
+        HistorySnapshot {
+            machine: self.machine.to_snapshot(),
+            past: self.past.iter().cloned().collect(),
+            future: self.future.clone(),
+        }
+    }
+
+    /// Restore the wrapped machine and its history. Checkpoints and listeners are kept.
+    fn restore_from(&mut self, snapshot: Self::Snapshot) -> Result<(), Error> {
+        self.machine.restore_from(snapshot.machine)?;
+        self.replace_entries(snapshot.past, snapshot.future);
+        Ok(())
+    }
+}
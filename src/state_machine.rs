@@ -0,0 +1,170 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+
+use crate::history::*;
+use crate::machine::*;
+use crate::parallel::*;
+
+/// Behaviour shared by every kind of state machine, so machines can wrap each other and code
+/// can be generic over machine kinds
+pub trait StateMachine<A> {
+    /// Type of the current state of the machine
+    type Value: Clone + PartialEq;
+
+    /// Type of the global state of the machine
+    type Context: Clone;
+
+    /// Type of the snapshots of the machine, holding its state and context along with the
+    /// states and contexts of any nested regions
+    type Snapshot: Clone;
+
+    /// Send an action to the state machine
+    fn transition(&mut self, action: &A);
+
+    /// Current state of the machine
+    fn value(&self) -> Self::Value;
+
+    /// Global state of the machine
+    fn context(&self) -> Self::Context;
+
+    /// Current state and context, including nested regions, to be restored later
+    fn snapshot(&self) -> Self::Snapshot;
+
+    /// Return to a snapshot taken by `snapshot`, without running any hooks
+    fn restore_snapshot(&mut self, snapshot: Self::Snapshot);
+
+    /// State and context held by a snapshot
+    fn snapshot_parts(snapshot: &Self::Snapshot) -> (Self::Value, Self::Context);
+
+    /// Run the `on_exit` hooks of the states left since `previous` and the `on_entry` hooks
+    /// of the states entered, for their side effects only. The current context is unchanged.
+    fn run_hooks(&self, _previous: &Self::Value, _action: &A) {}
+}
+
+impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> StateMachine<A> for Machine<A, S, C> {
+    type Value = S;
+    type Context = C;
+    type Snapshot = MachineState<S, C>;
+
+    fn transition(&mut self, action: &A) {
+        Machine::transition(self, action);
+    }
+
+    fn value(&self) -> S {
+        self.value
+    }
+
+    fn context(&self) -> C {
+        self.context
+    }
+
+    fn snapshot(&self) -> MachineState<S, C> {
+        self.save_state()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: MachineState<S, C>) {
+        self.restore_state(snapshot);
+    }
+
+    fn snapshot_parts(snapshot: &MachineState<S, C>) -> (S, C) {
+        (snapshot.value, snapshot.context)
+    }
+
+    fn run_hooks(&self, previous: &S, action: &A) {
+        if *previous == self.value {
+            return;
+        }
+
+        if let Some(transition) = self.definition.states.get(previous) {
+            if let Some(fn_on_exit) = transition.on_exit {
+                fn_on_exit(self.context, *action, self.value);
+            }
+        }
+
+        if let Some(transition) = self.definition.states.get(&self.value) {
+            if let Some(fn_on_entry) = transition.on_entry {
+                fn_on_entry(self.context, *action, self.value);
+            }
+        }
+    }
+}
+
+impl<A: Copy, S: Eq + Hash + Copy, C: Debug + Copy> StateMachine<A> for ParallelMachine<A, S, C> {
+    type Value = Vec<S>;
+
+    /// Context of every region, in order
+    type Context = Vec<C>;
+    type Snapshot = ParallelState<S, C>;
+
+    fn transition(&mut self, action: &A) {
+        ParallelMachine::transition(self, action);
+    }
+
+    fn value(&self) -> Vec<S> {
+        self.value.clone()
+    }
+
+    fn context(&self) -> Vec<C> {
+        self.machines.iter().map(|machine| machine.context).collect()
+    }
+
+    fn snapshot(&self) -> ParallelState<S, C> {
+        self.save_state()
+    }
+
+    fn restore_snapshot(&mut self, snapshot: ParallelState<S, C>) {
+        self.restore_state(snapshot);
+    }
+
+    fn snapshot_parts(snapshot: &ParallelState<S, C>) -> (Vec<S>, Vec<C>) {
+        snapshot.machines.iter().map(|machine| (machine.value, machine.context)).unzip()
+    }
+
+    fn run_hooks(&self, previous: &Vec<S>, action: &A) {
+        for (machine, previous) in self.machines.iter().zip(previous.iter()) {
+            machine.run_hooks(previous, action);
+        }
+    }
+}
+
+impl<A, S, C, M> StateMachine<A> for HistoryMachine<A, S, C, M>
+where
+    A: Copy,
+    S: Clone + PartialEq,
+    C: Clone,
+    M: StateMachine<A, Value = S, Context = C>,
+{
+    type Value = S;
+    type Context = C;
+    type Snapshot = M::Snapshot;
+
+    /// Send an action to the wrapped machine, recording it in the history
+    fn transition(&mut self, action: &A) {
+        HistoryMachine::transition(self, action);
+    }
+
+    fn value(&self) -> S {
+        self.machine.value()
+    }
+
+    fn context(&self) -> C {
+        self.machine.context()
+    }
+
+    fn snapshot(&self) -> M::Snapshot {
+        self.machine.snapshot()
+    }
+
+    /// Restore the wrapped machine without recording a step in the history
+    fn restore_snapshot(&mut self, snapshot: M::Snapshot) {
+        self.machine.restore_snapshot(snapshot);
+    }
+
+    fn snapshot_parts(snapshot: &M::Snapshot) -> (S, C) {
+        M::snapshot_parts(snapshot)
+    }
+
+    fn run_hooks(&self, previous: &S, action: &A) {
+        self.machine.run_hooks(previous, action);
+    }
+}
@@ -0,0 +1,147 @@
+use std::marker::PhantomData;
+
+use crate::error::*;
+use crate::machine::*;
+use crate::state_machine::*;
+
+/// A node in the undo tree of a machine, holding snapshots of type `T`
+#[derive(Debug, Clone)]
+pub struct UndoNode<A, T> {
+    /// Node this one branched off from. `None` for the root.
+    pub parent: Option<usize>,
+
+    /// Nodes reached from this one, oldest branch first
+    pub children: Vec<usize>,
+
+    /// The action that led from the parent to this node. `None` for the root.
+    pub action: Option<A>,
+
+    /// Snapshot of the machine at this node
+    pub snapshot: T,
+
+    /// Child followed by `redo`, the branch most recently visited
+    redo: Option<usize>,
+}
+
+/// Keep every branch of the history of a machine. Sending an action after an undo starts a new
+/// branch instead of discarding the steps that were undone. Wraps a `Machine` by default, or
+/// any other `StateMachine`.
+#[derive(Debug)]
+pub struct UndoTreeMachine<A, S, C, M = Machine<A, S, C>>
+where
+    M: StateMachine<A>,
+{
+    pub machine: M,
+    pub nodes: Vec<UndoNode<A, M::Snapshot>>,
+
+    /// Node matching the current state of the machine
+    pub current: usize,
+
+    marker: PhantomData<(S, C)>,
+}
+
+impl<A, S, C, M> UndoTreeMachine<A, S, C, M>
+where
+    A: Copy,
+    M: StateMachine<A, Value = S, Context = C>,
+{
+    /// Create a new state machine
+    pub fn new(machine: M) -> Self {
+        let root = UndoNode {
+            parent: None,
+            children: vec![],
+            action: None,
+            snapshot: machine.snapshot(),
+            redo: None,
+        };
+        UndoTreeMachine { machine, nodes: vec![root], current: 0, marker: PhantomData }
+    }
+
+    /// Send an action to the state machine, adding a node below the current one
+    pub fn transition(&mut self, action: &A) {
+        self.machine.transition(action);
+
+        let node = self.nodes.len();
+        self.nodes.push(UndoNode {
+            parent: Some(self.current),
+            children: vec![],
+            action: Some(*action),
+            snapshot: self.machine.snapshot(),
+            redo: None,
+        });
+
+        let parent = &mut self.nodes[self.current];
+        parent.children.push(node);
+        parent.redo = Some(node);
+        self.current = node;
+    }
+
+    /// Move to the parent of the current node
+    pub fn undo(&mut self) {
+        if let Some(parent) = self.nodes[self.current].parent {
+            self.nodes[parent].redo = Some(self.current);
+            self.restore(parent);
+        }
+    }
+
+    /// Move to the most recently visited child of the current node
+    pub fn redo(&mut self) {
+        if let Some(child) = self.nodes[self.current].redo {
+            self.restore(child);
+        }
+    }
+
+    /// Nodes branching off from `node`, oldest first
+    pub fn branches(&self, node: usize) -> &[usize] {
+        match self.nodes.get(node) {
+            Some(node) => &node.children,
+            None => &[],
+        }
+    }
+
+    /// Move to the next sibling branch of the current node
+    pub fn next_branch(&mut self) {
+        self.sibling(1);
+    }
+
+    /// Move to the previous sibling branch of the current node
+    pub fn previous_branch(&mut self) {
+        self.sibling(-1);
+    }
+
+    /// Jump to any node of the tree. Redo from its ancestors follows the path to this node.
+    pub fn goto(&mut self, node: usize) -> Result<(), Error> {
+        if node >= self.nodes.len() {
+            return Err(Error::NodeNotFound(node));
+        }
+
+        let mut child = node;
+        while let Some(parent) = self.nodes[child].parent {
+            self.nodes[parent].redo = Some(child);
+            child = parent;
+        }
+
+        self.restore(node);
+        Ok(())
+    }
+
+    fn sibling(&mut self, offset: isize) {
+        if let Some(parent) = self.nodes[self.current].parent {
+            let siblings = &self.nodes[parent].children;
+            if let Some(index) = siblings.iter().position(|&node| node == self.current) {
+                let target = index as isize + offset;
+                if target >= 0 && (target as usize) < siblings.len() {
+                    let node = siblings[target as usize];
+                    self.nodes[parent].redo = Some(node);
+                    self.restore(node);
+                }
+            }
+        }
+    }
+
+    /// Set the machine to the snapshot stored at `node`
+    fn restore(&mut self, node: usize) {
+        self.machine.restore_snapshot(self.nodes[node].snapshot.clone());
+        self.current = node;
+    }
+}
@@ -0,0 +1,493 @@
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ptr;
+
+use serde_json::{json, Map, Value};
+
+use crate::error::*;
+use crate::machine::*;
+use crate::parallel::*;
+
+/// Names of the states, events, actions and guards shared with xstate configs. Configs refer
+/// to actions and guards by name, so they are resolved to fn pointers through the registry.
+#[derive(Debug, Clone)]
+pub struct XstateRegistry<A, S, C> {
+    pub states: Vec<(String, S)>,
+    pub events: Vec<(String, A)>,
+    pub actions: Vec<(String, Hook<A, S, C>)>,
+    pub guards: Vec<(String, Guard<A, S, C>)>,
+}
+
+impl<A, S, C> Default for XstateRegistry<A, S, C> {
+    fn default() -> Self {
+        XstateRegistry { states: vec![], events: vec![], actions: vec![], guards: vec![] }
+    }
+}
+
+impl<A: Copy + PartialEq, S: Eq + Hash + Copy + Debug, C: Debug + Copy> XstateRegistry<A, S, C> {
+    /// Create an empty registry
+    pub fn new() -> Self {
+        XstateRegistry::default()
+    }
+
+    pub fn add_state(&mut self, name: &str, state: S) {
+        self.states.push((name.to_string(), state));
+    }
+
+    pub fn add_event(&mut self, name: &str, event: A) {
+        self.events.push((name.to_string(), event));
+    }
+
+    /// Name an entry, exit or transition action
+    pub fn add_action(&mut self, name: &str, action: Hook<A, S, C>) {
+        self.actions.push((name.to_string(), action));
+    }
+
+    pub fn add_guard(&mut self, name: &str, guard: Guard<A, S, C>) {
+        self.guards.push((name.to_string(), guard));
+    }
+
+    fn state(&self, name: &str) -> Result<S, Error> {
+        match self.states.iter().find(|(state_name, _)| state_name == name) {
+            Some((_, state)) => Ok(*state),
+            None => Err(invalid(format!("unknown state `{}`", name))),
+        }
+    }
+
+    fn state_name(&self, state: S) -> Result<&str, Error> {
+        match self.states.iter().find(|(_, named)| *named == state) {
+            Some((name, _)) => Ok(name),
+            None => Err(invalid(format!("state {:?} has no name", state))),
+        }
+    }
+
+    fn event(&self, name: &str) -> Result<A, Error> {
+        match self.events.iter().find(|(event_name, _)| event_name == name) {
+            Some((_, event)) => Ok(*event),
+            None => Err(invalid(format!("unknown event `{}`", name))),
+        }
+    }
+
+    fn action(&self, name: &str) -> Result<Hook<A, S, C>, Error> {
+        match self.actions.iter().find(|(action_name, _)| action_name == name) {
+            Some((_, action)) => Ok(*action),
+            None => Err(invalid(format!("unknown action `{}`", name))),
+        }
+    }
+
+    fn action_name(&self, action: Hook<A, S, C>) -> Result<&str, Error> {
+        match self.actions.iter().find(|(_, named)| ptr::fn_addr_eq(*named, action)) {
+            Some((name, _)) => Ok(name),
+            None => Err(invalid("an action has no name".to_string())),
+        }
+    }
+
+    fn guard(&self, name: &str) -> Result<Guard<A, S, C>, Error> {
+        match self.guards.iter().find(|(guard_name, _)| guard_name == name) {
+            Some((_, guard)) => Ok(*guard),
+            None => Err(invalid(format!("unknown guard `{}`", name))),
+        }
+    }
+
+    fn guard_name(&self, guard: Guard<A, S, C>) -> Result<&str, Error> {
+        match self.guards.iter().find(|(_, named)| ptr::fn_addr_eq(*named, guard)) {
+            Some((name, _)) => Ok(name),
+            None => Err(invalid("a guard has no name".to_string())),
+        }
+    }
+}
+
+impl<A: Copy + PartialEq, S: Eq + Hash + Copy + Debug, C: Debug + Copy> Machine<A, S, C> {
+    /// Create a machine from an xstate config, resolving every name through `registry`.
+    /// Parallel states become nested regions and history states resume the last state of
+    /// their region. Nested compound states that are not parallel are not supported, and a
+    /// parallel root is imported with `ParallelMachine::from_xstate`.
+    pub fn from_xstate(
+        config: &Value,
+        registry: &XstateRegistry<A, S, C>,
+        context: C,
+    ) -> Result<Self, Error> {
+        let id = config.get("id").and_then(Value::as_str).unwrap_or("machine");
+        if config.get("type").and_then(Value::as_str) == Some("parallel") {
+            return Err(invalid(format!("machine `{}` is parallel", id)));
+        }
+        import_machine(id, config, registry, context)
+    }
+
+    /// Export the machine as an xstate config. Transitions of `on` functions are found by
+    /// sending every event of `registry` to each state with the current context.
+    pub fn to_xstate(&self, registry: &XstateRegistry<A, S, C>) -> Result<Value, Error> {
+        let mut config = Map::new();
+        config.insert("id".to_string(), json!(self.id));
+        config.extend(export_machine(self, registry)?);
+        Ok(Value::Object(config))
+    }
+}
+
+impl<A: Copy + PartialEq, S: Eq + Hash + Copy + Debug, C: Debug + Copy> ParallelMachine<A, S, C> {
+    /// Create a machine from the xstate config of a parallel root, each of its states becoming
+    /// a region with its own copy of `context`
+    pub fn from_xstate(
+        config: &Value,
+        registry: &XstateRegistry<A, S, C>,
+        context: C,
+    ) -> Result<Self, Error> {
+        let id = config.get("id").and_then(Value::as_str).unwrap_or("machine");
+        if config.get("type").and_then(Value::as_str) != Some("parallel") {
+            return Err(invalid(format!("machine `{}` is not parallel", id)));
+        }
+        let machines = match config.get("states").and_then(Value::as_object) {
+            Some(regions) => import_regions(regions, registry, context)?,
+            None => return Err(invalid(format!("machine `{}` has no states", id))),
+        };
+        Ok(ParallelMachine::new(id.to_string(), machines))
+    }
+
+    /// Export the machine as the xstate config of a parallel root
+    pub fn to_xstate(&self, registry: &XstateRegistry<A, S, C>) -> Result<Value, Error> {
+        let mut config = Map::new();
+        config.insert("id".to_string(), json!(self.id));
+        config.insert("type".to_string(), json!("parallel"));
+        config.insert("states".to_string(), Value::Object(export_regions(self, registry)?));
+        Ok(Value::Object(config))
+    }
+}
+
+fn invalid(reason: String) -> Error {
+    Error::InvalidConfig(reason)
+}
+
+/// A single value, or every item of an array
+fn as_list(value: Option<&Value>) -> Vec<&Value> {
+    match value {
+        Some(Value::Array(items)) => items.iter().collect(),
+        Some(value) => vec![value],
+        None => vec![],
+    }
+}
+
+fn as_name<'a>(value: &'a Value, key: &str) -> Result<&'a str, Error> {
+    value.as_str().ok_or_else(|| invalid(format!("`{}` must be a name", key)))
+}
+
+fn import_machine<A, S, C>(
+    id: &str,
+    config: &Value,
+    registry: &XstateRegistry<A, S, C>,
+    context: C,
+) -> Result<Machine<A, S, C>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    let initial = match config.get("initial") {
+        Some(initial) => registry.state(as_name(initial, "initial")?)?,
+        None => return Err(invalid(format!("machine `{}` has no initial state", id))),
+    };
+    let states = match config.get("states").and_then(Value::as_object) {
+        Some(states) => states,
+        None => return Err(invalid(format!("machine `{}` has no states", id))),
+    };
+
+    let mut machine = Machine::new(id.to_string(), initial, context);
+    for (name, state_config) in states {
+        let state = registry.state(name)?;
+        let kind = state_config.get("type").and_then(Value::as_str);
+        let transition = Transition {
+            on_entry: import_hook(state_config.get("entry"), name, "entry", registry)?,
+            on_exit: import_hook(state_config.get("exit"), name, "exit", registry)?,
+            final_state: kind == Some("final"),
+            ..Default::default()
+        };
+
+        match (kind, state_config.get("states").and_then(Value::as_object)) {
+            (Some("parallel"), Some(regions)) => {
+                let machines = import_regions(regions, registry, context)?;
+                let regions = ParallelMachine::new(name.to_string(), machines);
+                machine.add_parallel_state(state, transition, regions);
+            }
+            (Some("history"), _) => {
+                let history = import_history(state, initial, name, state_config, registry)?;
+                machine.add_history_state(state, history);
+            }
+            (_, Some(_)) => {
+                return Err(invalid(format!("compound state `{}` is not parallel", name)));
+            }
+            _ => machine.add_state(state, transition),
+        }
+
+        let events = match state_config.get("on") {
+            Some(Value::Object(events)) => events.iter().collect(),
+            Some(_) => return Err(invalid(format!("`on` of state `{}` is not an object", name))),
+            None => vec![],
+        };
+        for (event_name, targets) in events {
+            let event = registry.event(event_name)?;
+            for target in as_list(Some(targets)) {
+                let transition = import_transition(event, state, target, registry)?;
+                machine.add_event_transition(state, transition);
+            }
+        }
+    }
+
+    // A machine starting in a history state has never been exited, so it starts in the target
+    if let Some(history) = machine.definition.history.get(&initial) {
+        machine.value = history.target;
+    }
+    Ok(machine)
+}
+
+/// Machines of the regions of a parallel state, each one with its own copy of `context`
+fn import_regions<A, S, C>(
+    regions: &Map<String, Value>,
+    registry: &XstateRegistry<A, S, C>,
+    context: C,
+) -> Result<Vec<Machine<A, S, C>>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    regions
+        .iter()
+        .map(|(region, config)| import_machine(region, config, registry, context))
+        .collect()
+}
+
+/// A history state, shallow unless `history` is `deep`. Without a `target` it resumes the
+/// initial state of its machine when the machine has never been exited.
+fn import_history<A, S, C>(
+    state: S,
+    initial: S,
+    name: &str,
+    config: &Value,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<HistoryState<S>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    let deep = match config.get("history").and_then(Value::as_str) {
+        Some("deep") => true,
+        Some("shallow") | None => false,
+        Some(other) => return Err(invalid(format!("unknown history `{}`", other))),
+    };
+    let target = match config.get("target") {
+        Some(target) => registry.state(as_name(target, "target")?)?,
+        None if state != initial => initial,
+        None => return Err(invalid(format!("initial history state `{}` has no target", name))),
+    };
+    Ok(HistoryState { target, deep })
+}
+
+/// Entry or exit action of a state. Only a single action per state is supported.
+fn import_hook<A, S, C>(
+    value: Option<&Value>,
+    state: &str,
+    key: &str,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<Option<Hook<A, S, C>>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    match as_list(value).as_slice() {
+        [] => Ok(None),
+        [name] => Ok(Some(registry.action(as_name(name, key)?)?)),
+        _ => Err(invalid(format!("state `{}` has more than one {} action", state, key))),
+    }
+}
+
+/// A transition given as a target name, or as an object with `target`, `guard` (or `cond`)
+/// and `actions`. Transitions without a target stay in `source`.
+fn import_transition<A, S, C>(
+    event: A,
+    source: S,
+    config: &Value,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<EventTransition<A, S, C>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    if let Value::String(target) = config {
+        let target = registry.state(target)?;
+        return Ok(EventTransition { event, target, guard: None, actions: vec![] });
+    }
+
+    if !config.is_object() {
+        return Err(invalid(format!("invalid transition {}", config)));
+    }
+
+    let target = match config.get("target") {
+        Some(target) => registry.state(as_name(target, "target")?)?,
+        None => source,
+    };
+    let guard = match config.get("guard").or_else(|| config.get("cond")) {
+        Some(guard) => Some(registry.guard(as_name(guard, "guard")?)?),
+        None => None,
+    };
+    let actions = as_list(config.get("actions"))
+        .into_iter()
+        .map(|action| registry.action(as_name(action, "actions")?))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(EventTransition { event, target, guard, actions })
+}
+
+/// `initial` and `states` of the config of a machine
+fn export_machine<A, S, C>(
+    machine: &Machine<A, S, C>,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<Map<String, Value>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    let definition = &machine.definition;
+    let mut states = Map::new();
+
+    for (state, transition) in definition.states.iter() {
+        let mut config = Map::new();
+        if let Some(fn_on_entry) = transition.on_entry {
+            config.insert("entry".to_string(), json!(registry.action_name(fn_on_entry)?));
+        }
+        if let Some(fn_on_exit) = transition.on_exit {
+            config.insert("exit".to_string(), json!(registry.action_name(fn_on_exit)?));
+        }
+        if transition.final_state {
+            config.insert("type".to_string(), json!("final"));
+        }
+
+        if let Some(regions) = machine.regions.get(state) {
+            config.insert("type".to_string(), json!("parallel"));
+            config.insert("states".to_string(), Value::Object(export_regions(regions, registry)?));
+        }
+
+        let events = export_events(machine, *state, transition, registry)?;
+        if !events.is_empty() {
+            config.insert("on".to_string(), Value::Object(events));
+        }
+        states.insert(registry.state_name(*state)?.to_string(), Value::Object(config));
+    }
+
+    for (state, history) in definition.history.iter() {
+        let mut config = Map::new();
+        config.insert("type".to_string(), json!("history"));
+        config.insert("history".to_string(), json!(if history.deep { "deep" } else { "shallow" }));
+        config.insert("target".to_string(), json!(registry.state_name(history.target)?));
+        states.insert(registry.state_name(*state)?.to_string(), Value::Object(config));
+    }
+
+    let mut config = Map::new();
+    config.insert("initial".to_string(), json!(registry.state_name(definition.initial)?));
+    config.insert("states".to_string(), Value::Object(states));
+    Ok(config)
+}
+
+/// `states` of the config of a parallel state, one per region
+fn export_regions<A, S, C>(
+    regions: &ParallelMachine<A, S, C>,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<Map<String, Value>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    let mut states = Map::new();
+    for region in regions.machines.iter() {
+        states.insert(region.id.clone(), Value::Object(export_machine(region, registry)?));
+    }
+    Ok(states)
+}
+
+/// `on` of the config of a state, from its `on` function and its event transitions
+fn export_events<A, S, C>(
+    machine: &Machine<A, S, C>,
+    state: S,
+    transition: &Transition<A, S, C>,
+    registry: &XstateRegistry<A, S, C>,
+) -> Result<Map<String, Value>, Error>
+where
+    A: Copy + PartialEq,
+    S: Eq + Hash + Copy + Debug,
+    C: Debug + Copy,
+{
+    let state_guard = match transition.guard {
+        Some(fn_guard) => Some(registry.guard_name(fn_guard)?),
+        None => None,
+    };
+    let context_action = match transition.context {
+        Some(fn_context) => vec![registry.action_name(fn_context)?],
+        None => vec![],
+    };
+    let event_transitions = machine.definition.events.get(&state);
+
+    let mut events = Map::new();
+    for (name, event) in registry.events.iter() {
+        let mut targets = vec![];
+
+        if let Some(fn_on) = transition.on {
+            let target = fn_on(machine.context, *event, state);
+            if target != state {
+                let target = registry.state_name(target)?;
+                targets.push(export_transition(target, state_guard, &context_action));
+            }
+        }
+
+        for event_transition in event_transitions.into_iter().flatten() {
+            if event_transition.event != *event {
+                continue;
+            }
+
+            let guard = match (state_guard, event_transition.guard) {
+                (Some(_), Some(_)) => {
+                    return Err(invalid(format!("state {:?} has two guards", state)));
+                }
+                (_, Some(fn_guard)) => Some(registry.guard_name(fn_guard)?),
+                (guard, None) => guard,
+            };
+            let actions = event_transition
+                .actions
+                .iter()
+                .map(|fn_action| registry.action_name(*fn_action))
+                .collect::<Result<Vec<_>, _>>()?;
+            let target = registry.state_name(event_transition.target)?;
+            targets.push(export_transition(target, guard, &actions));
+        }
+
+        match targets.len() {
+            0 => {}
+            1 => {
+                events.insert(name.clone(), targets.remove(0));
+            }
+            _ => {
+                events.insert(name.clone(), Value::Array(targets));
+            }
+        }
+    }
+    Ok(events)
+}
+
+/// A transition as a target name, or as an object when it has a guard or actions
+fn export_transition(target: &str, guard: Option<&str>, actions: &[&str]) -> Value {
+    if guard.is_none() && actions.is_empty() {
+        return json!(target);
+    }
+
+    let mut config = Map::new();
+    config.insert("target".to_string(), json!(target));
+    if let Some(guard) = guard {
+        config.insert("guard".to_string(), json!(guard));
+    }
+    if !actions.is_empty() {
+        config.insert("actions".to_string(), json!(actions));
+    }
+    Value::Object(config)
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use rstate::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Counter whose count must stay below 3 while it is active
+    fn bounded(id: &str) -> Machine<Action, State, Context> {
+        let mut machine = counter(id);
+        let active = machine.definition.states[&State::Active];
+        machine.add_state(
+            State::Active,
+            Transition { invariant: Some(|context, _state| context.count < 3), ..active },
+        );
+        machine
+    }
+
+    /// Machine whose entry hook panics once it finishes
+    fn fragile(id: &str) -> Machine<Action, State, Context> {
+        let mut machine = Machine::<Action, State, Context>::new(
+            id.to_string(),
+            State::Idle,
+            Context { count: 0 },
+        );
+        machine.add_state(
+            State::Idle,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Finish => State::Finished,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            State::Finished,
+            Transition {
+                on_entry: Some(|_context, _action, _state| panic!("entry hook failed")),
+                ..Default::default()
+            },
+        );
+        machine
+    }
+
+    #[test]
+    fn rollback_on_panic() {
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "atomic".to_string(),
+            vec![counter("counter"), fragile("fragile")],
+        );
+        let hooks = Arc::new(Mutex::new(vec![]));
+        let log = Arc::clone(&hooks);
+        machine.subscribe(move |event| log.lock().unwrap().push(event.clone()));
+
+        assert_eq!(machine.try_transition(&Action::Increment(1)), Ok(()));
+        assert_eq!(machine.value, vec![State::Active, State::Idle]);
+        let committed = hooks.lock().unwrap().clone();
+
+        // The hooks of the action rolled back are not reported
+        let result = machine.try_transition(&Action::Finish);
+        assert_eq!(result, Err(Error::Panicked("fragile".to_string())));
+        assert_eq!(machine.value, vec![State::Active, State::Idle]);
+        assert_eq!(machine.machines[0].context.count, 1);
+        assert_eq!(*hooks.lock().unwrap(), committed);
+
+        assert_eq!(machine.try_transition(&Action::Increment(1)), Ok(()));
+        let hook = HookEvent {
+            machine: "counter".to_string(),
+            hook: HookKind::Context,
+            state: State::Active,
+        };
+        assert_eq!(hooks.lock().unwrap().last(), Some(&hook));
+    }
+
+    #[test]
+    fn rollback_on_invariant_violation() {
+        let mut machine = ParallelMachine::<Action, State, Context>::with_context(
+            "atomic".to_string(),
+            vec![bounded("first"), bounded("second")],
+            Context { count: 0 },
+        );
+
+        assert_eq!(machine.try_transition(&Action::Increment(1)), Ok(()));
+        assert_eq!(machine.context.unwrap().count, 2);
+
+        let result = machine.try_transition(&Action::Increment(1));
+        assert_eq!(result, Err(Error::InvariantViolated("first".to_string())));
+        assert_eq!(machine.value, vec![State::Active, State::Active]);
+        assert_eq!(machine.context.unwrap().count, 2);
+        assert_eq!(machine.machines[1].context.count, 2);
+    }
+
+    #[test]
+    fn rollback_nested_regions() {
+        let mut outer = Machine::<Action, State, Context>::new(
+            "outer".to_string(),
+            State::Active,
+            Context { count: 0 },
+        );
+        outer.add_parallel_state(
+            State::Active,
+            Transition::default(),
+            ParallelMachine::new("inner".to_string(), vec![counter("counter")]),
+        );
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "atomic".to_string(),
+            vec![outer, fragile("fragile")],
+        );
+
+        let result = machine.try_transition(&Action::Finish);
+        assert_eq!(result, Err(Error::Panicked("fragile".to_string())));
+        assert_eq!(machine.configuration(), vec![State::Active, State::Active, State::Idle]);
+
+        assert_eq!(machine.try_transition(&Action::Increment(1)), Ok(()));
+        let inner = &machine.machines[0].regions[&State::Active];
+        assert_eq!(inner.machines[0].context.count, 1);
+    }
+}
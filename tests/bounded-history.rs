@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use rstate::*;
+
+    #[test]
+    fn bounded_by_capacity() {
+        let mut history_machine =
+            HistoryMachine::<Action, State, Context>::with_capacity(counter("counter"), 3);
+
+        for _ in 0..5 {
+            history_machine.transition(&Action::Increment(1));
+        }
+        assert_eq!(history_machine.machine.context.count, 5);
+        assert_eq!(history_machine.past.len(), 3);
+        assert_eq!(history_machine.stats.evicted, 2);
+
+        // Only the three most recent steps can be undone
+        for _ in 0..5 {
+            history_machine.undo();
+        }
+        assert_eq!(history_machine.machine.context.count, 2);
+
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 3);
+    }
+
+    #[test]
+    fn goto_past_capacity() {
+        let mut history_machine =
+            HistoryMachine::<Action, State, Context>::with_capacity(counter("counter"), 2);
+        history_machine.redo_policy = RedoPolicy::Keep;
+
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(2));
+        history_machine.undo();
+        history_machine.transition(&Action::Increment(4));
+        assert_eq!(history_machine.len(), 3);
+
+        // Redoing evicts the oldest entry, so the cursor stops short of the end
+        assert_eq!(history_machine.goto(history_machine.len()), Ok(()));
+        assert_eq!(history_machine.cursor(), 2);
+        assert!(!history_machine.can_redo());
+        assert_eq!(history_machine.stats.evicted, 1);
+    }
+
+    #[test]
+    fn bounded_by_memory() {
+        let entry_size = std::mem::size_of::<HistoryEntry<Action, MachineState<State, Context>>>()
+            - std::mem::size_of::<Context>()
+            + 10;
+        let mut history_machine = HistoryMachine::<Action, State, Context>::with_memory_limit(
+            counter("counter"),
+            entry_size * 2,
+        );
+
+        for _ in 0..4 {
+            history_machine.transition(&Action::Increment(1));
+        }
+        assert_eq!(history_machine.past.len(), 2);
+        assert_eq!(history_machine.stats.evicted, 2);
+        assert_eq!(history_machine.stats.evicted_bytes, entry_size * 2);
+    }
+}
@@ -0,0 +1,112 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Debug, Copy, Clone)]
+    enum Action {
+        Start,
+        Score(u8),
+        Submit,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Menu,
+        Playing,
+        Submitted,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {
+        score: u8,
+    }
+
+    #[test]
+    fn checkpoints() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "game".to_string(),
+            State::Menu,
+            Context { score: 0 },
+        );
+        machine.add_state(
+            State::Menu,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Start => State::Playing,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            State::Playing,
+            Transition {
+                context: Some(|mut context, action, _state| {
+                    if let Action::Score(points) = action {
+                        context.score += points;
+                    }
+                    context
+                }),
+                on: Some(|_context, action, state| match action {
+                    Action::Submit => State::Submitted,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(State::Submitted, Transition { ..Default::default() });
+
+        let mut history_machine = HistoryMachine::<Action, State, Context>::new(machine);
+
+        history_machine.transition(&Action::Start);
+        history_machine.checkpoint("level start");
+        history_machine.transition(&Action::Score(3));
+        history_machine.transition(&Action::Score(4));
+        history_machine.checkpoint("before submit");
+        history_machine.transition(&Action::Submit);
+        assert_eq!(history_machine.machine.value, State::Submitted);
+
+        let names: Vec<&str> = history_machine
+            .checkpoints()
+            .iter()
+            .map(|checkpoint| checkpoint.name.as_str())
+            .collect();
+        assert_eq!(names, vec!["level start", "before submit"]);
+
+        assert_eq!(history_machine.restore("level start"), Ok(()));
+        assert_eq!(history_machine.machine.value, State::Playing);
+        assert_eq!(history_machine.machine.context.score, 0);
+        assert!(history_machine.log().last().unwrap().action.is_none());
+
+        // Restores can be undone and redone like any other step
+        history_machine.undo();
+        assert_eq!(history_machine.machine.value, State::Submitted);
+        assert_eq!(history_machine.machine.context.score, 7);
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.score, 0);
+
+        assert_eq!(
+            history_machine.restore("game over"),
+            Err(Error::CheckpointNotFound("game over".to_string()))
+        );
+
+        // Saving under an existing name replaces the checkpoint
+        history_machine.transition(&Action::Score(1));
+        history_machine.checkpoint("level start");
+        assert_eq!(history_machine.checkpoints().len(), 2);
+        assert_eq!(history_machine.checkpoints()[0].snapshot.context.score, 1);
+
+        // Replay applies the restore where it was recorded
+        history_machine.machine.context.score = 0;
+        assert_eq!(history_machine.replay(true), Ok(()));
+        assert_eq!(history_machine.machine.value, State::Playing);
+        assert_eq!(history_machine.machine.context.score, 1);
+
+        // A restore logged last replays to the checkpoint, not to the live state
+        assert_eq!(history_machine.restore("before submit"), Ok(()));
+        history_machine.machine.context.score = 0;
+        assert_eq!(history_machine.replay(true), Ok(()));
+        assert_eq!(history_machine.machine.value, State::Playing);
+        assert_eq!(history_machine.machine.context.score, 7);
+    }
+}
@@ -0,0 +1,70 @@
+// Every test file includes this module, but none of them uses all of it
+#![allow(dead_code)]
+
+use rstate::*;
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Action {
+    Start,
+    Increment(u8),
+    Decrement(u8),
+    Finish,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum State {
+    Idle,
+    Active,
+    Finished,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Context {
+    pub count: u8,
+}
+
+impl EstimateSize for Context {
+    fn estimated_size(&self) -> usize {
+        10
+    }
+}
+
+/// Counter starting in `Active`, where it counts increments and decrements. `Finish` stops
+/// counting until `Start` resumes it.
+pub fn counter(id: &str) -> Machine<Action, State, Context> {
+    let mut machine =
+        Machine::<Action, State, Context>::new(id.to_string(), State::Active, Context { count: 0 });
+    machine.add_state(
+        State::Active,
+        Transition {
+            context: Some(|mut context, action, _state| {
+                match action {
+                    Action::Increment(val) => context.count += val,
+                    Action::Decrement(val) => context.count -= val,
+                    _ => {}
+                }
+                context
+            }),
+            on: Some(|_context, action, state| match action {
+                Action::Finish => State::Finished,
+                _ => state,
+            }),
+            ..Default::default()
+        },
+    );
+    machine.add_state(
+        State::Finished,
+        Transition {
+            on: Some(|_context, action, state| match action {
+                Action::Start => State::Active,
+                _ => state,
+            }),
+            ..Default::default()
+        },
+    );
+    machine
+}
+
+pub fn history_machine() -> HistoryMachine<Action, State, Context> {
+    HistoryMachine::<Action, State, Context>::new(counter("counter"))
+}
@@ -0,0 +1,161 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Tick,
+        Reset,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Counting(u8),
+        Stopped,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Context {
+        ticks: u32,
+        entries: u32,
+        exits: u32,
+    }
+
+    fn simulation() -> ParallelMachine<Action, State, Context> {
+        let machines = (0..200u8)
+            .map(|index| {
+                let mut machine = Machine::<Action, State, Context>::new(
+                    format!("region-{}", index),
+                    State::Counting(index % 4),
+                    Context { ticks: 0, entries: 0, exits: 0 },
+                );
+                for step in 0..4 {
+                    machine.add_state(
+                        State::Counting(step),
+                        Transition {
+                            context: Some(|mut context, _action, _state| {
+                                context.ticks += 1;
+                                context
+                            }),
+                            on: Some(|_context, action, state| match (action, state) {
+                                (Action::Tick, State::Counting(3)) => State::Stopped,
+                                (Action::Tick, State::Counting(step)) => State::Counting(step + 1),
+                                (Action::Reset, _) => State::Counting(0),
+                                _ => state,
+                            }),
+                            on_entry: Some(|mut context, _action, _state| {
+                                context.entries += 1;
+                                context
+                            }),
+                            on_exit: Some(|mut context, _action, _state| {
+                                context.exits += 1;
+                                context
+                            }),
+                            ..Default::default()
+                        },
+                    );
+                }
+                machine.add_state(
+                    State::Stopped,
+                    Transition {
+                        on: Some(|_context, action, state| match action {
+                            Action::Reset => State::Counting(0),
+                            _ => state,
+                        }),
+                        ..Default::default()
+                    },
+                );
+                machine
+            })
+            .collect();
+
+        ParallelMachine::<Action, State, Context>::new("simulation".to_string(), machines)
+    }
+
+    #[test]
+    fn concurrent_matches_sequential() {
+        let mut sequential = simulation();
+        let mut concurrent = simulation();
+        let pool = WorkerPool::new(8);
+
+        let actions = [
+            Action::Tick,
+            Action::Tick,
+            Action::Reset,
+            Action::Tick,
+            Action::Tick,
+            Action::Tick,
+            Action::Tick,
+            Action::Tick,
+        ];
+
+        for action in actions.iter() {
+            sequential.transition(action);
+            concurrent.transition_concurrent(action, &pool);
+
+            assert_eq!(sequential.value, concurrent.value);
+        }
+
+        for (left, right) in sequential.machines.iter().zip(concurrent.machines.iter()) {
+            assert_eq!(left.id, right.id);
+            assert_eq!(left.context, right.context);
+        }
+    }
+
+    #[test]
+    fn more_threads_than_regions() {
+        let mut machine = simulation();
+        machine.machines.truncate(3);
+
+        machine.transition_concurrent(&Action::Tick, &WorkerPool::new(16));
+        assert_eq!(
+            machine.value,
+            vec![State::Counting(1), State::Counting(2), State::Counting(3)]
+        );
+    }
+
+    #[test]
+    fn hooks_reported_in_region_order() {
+        let sequential_log = Arc::new(Mutex::new(vec![]));
+        let concurrent_log = Arc::new(Mutex::new(vec![]));
+
+        let mut sequential = simulation();
+        let log = Arc::clone(&sequential_log);
+        sequential.subscribe(move |event| log.lock().unwrap().push(event.clone()));
+
+        let mut concurrent = simulation();
+        let log = Arc::clone(&concurrent_log);
+        concurrent.subscribe(move |event| log.lock().unwrap().push(event.clone()));
+
+        let pool = WorkerPool::new(8);
+        for action in [Action::Tick, Action::Tick, Action::Reset, Action::Tick].iter() {
+            sequential.transition(action);
+            concurrent.transition_concurrent(action, &pool);
+        }
+
+        let sequential_log = sequential_log.lock().unwrap();
+        let concurrent_log = concurrent_log.lock().unwrap();
+        assert_eq!(*sequential_log, *concurrent_log);
+        assert_eq!(
+            sequential_log[..3],
+            [
+                HookEvent {
+                    machine: "region-0".to_string(),
+                    hook: HookKind::Context,
+                    state: State::Counting(0),
+                },
+                HookEvent {
+                    machine: "region-0".to_string(),
+                    hook: HookKind::Entry,
+                    state: State::Counting(1),
+                },
+                HookEvent {
+                    machine: "region-0".to_string(),
+                    hook: HookKind::Exit,
+                    state: State::Counting(0),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Pay,
+        Ship,
+        Confirm,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Checkout,
+        Complete,
+        Unpaid,
+        Paid,
+        Packing,
+        Shipped,
+        Unconfirmed,
+        Confirmed,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {}
+
+    fn region(
+        id: &str,
+        initial: State,
+        action: Action,
+        done: State,
+    ) -> Machine<Action, State, Context> {
+        let mut machine =
+            Machine::<Action, State, Context>::new(id.to_string(), initial, Context {});
+        let on: fn(Context, Action, State) -> State = match action {
+            Action::Pay => |_context, action, state| match action {
+                Action::Pay => State::Paid,
+                _ => state,
+            },
+            Action::Ship => |_context, action, state| match action {
+                Action::Ship => State::Shipped,
+                _ => state,
+            },
+            Action::Confirm => |_context, action, state| match action {
+                Action::Confirm => State::Confirmed,
+                _ => state,
+            },
+        };
+
+        machine.add_state(
+            initial,
+            Transition {
+                on: Some(on),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            done,
+            Transition {
+                final_state: true,
+                ..Default::default()
+            },
+        );
+        machine
+    }
+
+    fn checkout() -> ParallelMachine<Action, State, Context> {
+        ParallelMachine::<Action, State, Context>::new(
+            "checkout".to_string(),
+            vec![
+                region("payment", State::Unpaid, Action::Pay, State::Paid),
+                region("shipping", State::Packing, Action::Ship, State::Shipped),
+                region("confirmation", State::Unconfirmed, Action::Confirm, State::Confirmed),
+            ],
+        )
+    }
+
+    #[test]
+    fn parallel_machine_is_done() {
+        let mut machine = checkout();
+
+        assert!(!machine.is_done());
+        assert_eq!(machine.done_event(), "done.state.checkout");
+
+        machine.transition(&Action::Pay);
+        machine.transition(&Action::Ship);
+        assert!(!machine.is_done());
+
+        machine.transition(&Action::Confirm);
+        assert!(machine.is_done());
+    }
+
+    #[test]
+    fn done_state_event() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "order".to_string(),
+            State::Checkout,
+            Context {},
+        );
+        machine.add_parallel_state(
+            State::Checkout,
+            Transition {
+                on_done: Some(|_context, event, state| match event {
+                    "done.state.checkout" => State::Complete,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+            checkout(),
+        );
+        machine.add_state(
+            State::Complete,
+            Transition {
+                final_state: true,
+                ..Default::default()
+            },
+        );
+
+        machine.transition(&Action::Ship);
+        machine.transition(&Action::Confirm);
+        assert_eq!(machine.value, State::Checkout);
+        assert!(!machine.is_done());
+
+        machine.transition(&Action::Pay);
+        assert_eq!(machine.value, State::Complete);
+        assert!(machine.is_done());
+    }
+
+    #[test]
+    fn regions_entered_done() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "order".to_string(),
+            State::Unpaid,
+            Context {},
+        );
+        machine.add_state(
+            State::Unpaid,
+            Transition {
+                on: Some(|_context, _action, _state| State::Checkout),
+                ..Default::default()
+            },
+        );
+        machine.add_parallel_state(
+            State::Checkout,
+            Transition {
+                on_done: Some(|_context, _event, _state| State::Complete),
+                ..Default::default()
+            },
+            ParallelMachine::<Action, State, Context>::new(
+                "checkout".to_string(),
+                vec![region("payment", State::Paid, Action::Pay, State::Paid)],
+            ),
+        );
+        machine.add_state(
+            State::Complete,
+            Transition {
+                final_state: true,
+                ..Default::default()
+            },
+        );
+
+        machine.transition(&Action::Pay);
+        assert_eq!(machine.value, State::Complete);
+    }
+
+    #[test]
+    fn no_regions_not_done() {
+        let machine = ParallelMachine::<Action, State, Context>::new("empty".to_string(), vec![]);
+        assert!(!machine.is_done());
+    }
+}
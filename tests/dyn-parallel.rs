@@ -0,0 +1,110 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[test]
+    fn dyn_parallel_machine() {
+        #[derive(Copy, Clone, Debug)]
+        enum Action {
+            Bullets,
+            Numbers,
+            ToggleBold,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ToggleState {
+            On,
+            Off,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ListState {
+            None,
+            Numbers,
+            Bullets,
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        struct ToggleContext {}
+
+        #[derive(Debug, Clone, Copy)]
+        struct ListContext {
+            changes: u8,
+        }
+
+        let mut bold_machine = Machine::<Action, ToggleState, ToggleContext>::new(
+            "bold".to_string(),
+            ToggleState::Off,
+            ToggleContext {},
+        );
+        bold_machine.add_state(
+            ToggleState::Off,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => ToggleState::On,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        bold_machine.add_state(
+            ToggleState::On,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => ToggleState::Off,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut list_machine = Machine::<Action, ListState, ListContext>::new(
+            "list".to_string(),
+            ListState::None,
+            ListContext { changes: 0 },
+        );
+        for state in [ListState::None, ListState::Numbers, ListState::Bullets].iter() {
+            list_machine.add_state(
+                *state,
+                Transition {
+                    on: Some(|_context, action, state| match action {
+                        Action::Bullets => ListState::Bullets,
+                        Action::Numbers => ListState::Numbers,
+                        _ => state,
+                    }),
+                    on_entry: Some(|mut context, _action, _state| {
+                        context.changes += 1;
+                        context
+                    }),
+                    ..Default::default()
+                },
+            );
+        }
+
+        let mut machine = DynParallelMachine::<Action>::new(
+            "parallel".to_string(),
+            vec![Box::new(bold_machine), Box::new(list_machine)],
+        );
+
+        assert_eq!(machine.value::<ToggleState, ToggleContext>(0), Some(ToggleState::Off));
+        assert_eq!(machine.value::<ListState, ListContext>(1), Some(ListState::None));
+
+        machine.transition(&Action::ToggleBold);
+        machine.transition(&Action::Numbers);
+        assert_eq!(machine.value::<ToggleState, ToggleContext>(0), Some(ToggleState::On));
+        assert_eq!(machine.value::<ListState, ListContext>(1), Some(ListState::Numbers));
+
+        machine.transition(&Action::Bullets);
+        let list = machine.machine::<ListState, ListContext>(1).unwrap();
+        assert_eq!(list.value, ListState::Bullets);
+        assert_eq!(list.context.changes, 2);
+
+        // Asking for the wrong types gives nothing back
+        assert_eq!(machine.value::<ListState, ListContext>(0), None);
+        assert_eq!(machine.value::<ToggleState, ToggleContext>(2), None);
+
+        // Like `ParallelMachine`, a machine without regions is never done
+        let empty = DynParallelMachine::<Action>::new("empty".to_string(), vec![]);
+        assert!(!empty.is_done());
+    }
+}
@@ -0,0 +1,290 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Start,
+        Finish,
+        Cancel,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Idle,
+        Running,
+        Done,
+        Working,
+        Finished,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {}
+
+    fn task() -> Machine<Action, State, Context> {
+        let mut machine =
+            Machine::<Action, State, Context>::new("task".to_string(), State::Idle, Context {});
+        machine.add_state(
+            State::Idle,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Start => State::Running,
+                    Action::Cancel => State::Done,
+                    _ => state,
+                }),
+                on_exit: Some(|context, _action, _state| context),
+                description: Some("Waiting for work: no worker yet"),
+                exit_name: Some("assignWorker"),
+                note: Some("Cancelling an idle task\nskips running it"),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            State::Running,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Finish | Action::Cancel => State::Done,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(State::Done, Transition { final_state: true, ..Default::default() });
+        machine
+    }
+
+    fn worker(id: &str) -> Machine<Action, State, Context> {
+        let mut machine =
+            Machine::<Action, State, Context>::new(id.to_string(), State::Working, Context {});
+        machine.add_state(
+            State::Working,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Finish => State::Finished,
+                    _ => state,
+                }),
+                on_entry: Some(|context, _action, _state| context),
+                ..Default::default()
+            },
+        );
+        machine.add_state(State::Finished, Transition { final_state: true, ..Default::default() });
+        machine
+    }
+
+    const ACTIONS: [Action; 3] = [Action::Start, Action::Finish, Action::Cancel];
+
+    #[test]
+    fn machine_to_dot() {
+        let expected = r#"digraph "task" {
+    rankdir=LR;
+    node [shape=box, style=rounded];
+    "task.[*]" [shape=point];
+    "task.[*]" -> "task.Idle";
+    "task.Idle" [label="Idle"];
+    "task.Running" [label="Running"];
+    "task.Done" [label="Done", peripheries=2];
+    "task.Idle" -> "task.Running" [label="Start"];
+    "task.Idle" -> "task.Done" [label="Cancel"];
+    "task.Running" -> "task.Done" [label="Finish, Cancel"];
+}
+"#;
+        assert_eq!(task().to_dot(&ACTIONS), expected);
+    }
+
+    #[test]
+    fn parallel_regions_as_clusters() {
+        let mut machine = task();
+        let regions = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+        machine.add_parallel_state(
+            State::Running,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Cancel => State::Done,
+                    _ => state,
+                }),
+                on_done: Some(|_context, _event, _state| State::Done),
+                ..Default::default()
+            },
+            regions,
+        );
+
+        let dot = machine.to_dot(&ACTIONS);
+        assert!(dot.contains("    subgraph \"cluster_task.Running\" {\n"));
+        assert!(dot.contains("        subgraph \"cluster_task.Running.first\" {\n"));
+        assert!(dot.contains(
+            "            \"task.Running.second.Working\" -> \"task.Running.second.Finished\" \
+             [label=\"Finish\"];\n"
+        ));
+        assert!(dot.contains(
+            "    \"task.Running\" -> \"task.Done\" [label=\"Cancel, done.state.workers\"];\n"
+        ));
+    }
+
+    #[test]
+    fn parallel_machine_to_dot() {
+        let machine = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+
+        let dot = machine.to_dot(&ACTIONS);
+        assert!(dot.starts_with("digraph \"workers\" {\n"));
+        assert!(dot.contains("    subgraph \"cluster_workers.second\" {\n"));
+        assert!(dot.contains(
+            "        \"workers.first.Finished\" [label=\"Finished\", peripheries=2];\n"
+        ));
+    }
+
+    #[test]
+    fn machine_to_mermaid() {
+        let expected = "stateDiagram-v2
+    state \"Idle\" as task_Idle
+    state \"Running\" as task_Running
+    state \"Done\" as task_Done
+    [*] --> task_Idle
+    task_Idle --> task_Running : Start
+    task_Idle --> task_Done : Cancel
+    task_Running --> task_Done : Finish, Cancel
+    task_Done --> [*]
+";
+        assert_eq!(task().to_mermaid(&ACTIONS), expected);
+    }
+
+    #[test]
+    fn parallel_regions_separated() {
+        let mut machine = task();
+        let regions = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+        machine.add_parallel_state(
+            State::Running,
+            Transition {
+                on_done: Some(|_context, _event, _state| State::Done),
+                ..Default::default()
+            },
+            regions,
+        );
+
+        let expected = "stateDiagram-v2
+    state \"Idle\" as task_Idle
+    state \"Running\" as task_Running
+    state \"Done\" as task_Done
+    [*] --> task_Idle
+    task_Idle --> task_Running : Start
+    task_Idle --> task_Done : Cancel
+    task_Running --> task_Done : done.state.workers
+    task_Done --> [*]
+    state task_Running {
+        state \"first\" as task_Running_first
+        [*] --> task_Running_first
+        state task_Running_first {
+            state \"Working\" as task_Running_first_Working
+            state \"Finished\" as task_Running_first_Finished
+            [*] --> task_Running_first_Working
+            task_Running_first_Working --> task_Running_first_Finished : Finish
+            task_Running_first_Finished --> [*]
+        }
+        --
+        state \"second\" as task_Running_second
+        [*] --> task_Running_second
+        state task_Running_second {
+            state \"Working\" as task_Running_second_Working
+            state \"Finished\" as task_Running_second_Finished
+            [*] --> task_Running_second_Working
+            task_Running_second_Working --> task_Running_second_Finished : Finish
+            task_Running_second_Finished --> [*]
+        }
+    }
+";
+        assert_eq!(machine.to_mermaid(&ACTIONS), expected);
+    }
+
+    #[test]
+    fn parallel_machine_to_mermaid() {
+        let machine = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+
+        let mermaid = machine.to_mermaid(&ACTIONS);
+        assert!(mermaid.starts_with("stateDiagram-v2\n    state \"workers\" as workers\n"));
+        assert!(mermaid.contains("    [*] --> workers\n    state workers {\n"));
+        assert!(mermaid.contains("        --\n"));
+    }
+
+    #[test]
+    fn machine_to_plantuml() {
+        let expected = "@startuml
+[*] --> task_Idle
+state \"Idle\" as task_Idle
+task_Idle : Waiting for work<U+003A> no worker yet
+task_Idle : exit / assignWorker
+note right of task_Idle
+    Cancelling an idle task
+    skips running it
+end note
+state \"Running\" as task_Running
+state \"Done\" as task_Done
+task_Idle --> task_Running : Start
+task_Idle --> task_Done : Cancel
+task_Running --> task_Done : Finish, Cancel
+task_Done --> [*]
+@enduml
+";
+        assert_eq!(task().to_plantuml(&ACTIONS), expected);
+    }
+
+    #[test]
+    fn parallel_regions_concurrent() {
+        let mut machine = task();
+        let regions = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+        machine.add_parallel_state(
+            State::Running,
+            Transition {
+                on_done: Some(|_context, _event, _state| State::Done),
+                description: Some("Workers run concurrently\nuntil both finish"),
+                ..Default::default()
+            },
+            regions,
+        );
+
+        let plantuml = machine.to_plantuml(&ACTIONS);
+        let expected = "state \"Running\" as task_Running {
+    [*] --> task_Running_first
+    state \"first\" as task_Running_first {
+        [*] --> task_Running_first_Working
+        state \"Working\" as task_Running_first_Working
+        task_Running_first_Working : entry
+        state \"Finished\" as task_Running_first_Finished
+        task_Running_first_Working --> task_Running_first_Finished : Finish
+        task_Running_first_Finished --> [*]
+    }
+    --
+    [*] --> task_Running_second
+";
+        assert!(plantuml.contains(expected));
+        let description = "}\ntask_Running : Workers run concurrently\\nuntil both finish\n";
+        assert!(plantuml.contains(description));
+        assert!(plantuml.contains("task_Running --> task_Done : done.state.workers\n"));
+    }
+
+    #[test]
+    fn parallel_machine_to_plantuml() {
+        let machine = ParallelMachine::new(
+            "workers".to_string(),
+            vec![worker("first"), worker("second")],
+        );
+
+        let plantuml = machine.to_plantuml(&ACTIONS);
+        assert!(plantuml.starts_with("@startuml\n[*] --> workers\n"));
+        assert!(plantuml.contains("state \"workers\" as workers {\n    [*] --> workers_first\n"));
+        assert!(plantuml.ends_with("    }\n}\n@enduml\n"));
+    }
+}
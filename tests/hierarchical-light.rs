@@ -72,10 +72,9 @@ mod tests {
             State::Red(Red::Walk),
             Transition {
                 context: Some(|mut context, action, _state| {
-                    match action {
-                        Action::PedestrianTimer => context.timer -= 1,
-                        _ => {}
-                    };
+                    if let Action::PedestrianTimer = action {
+                        context.timer -= 1;
+                    }
                     context
                 }),
                 on: Some(|context, action, state| match action {
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+
+    #[test]
+    fn filter_predicate() {
+        let mut history_machine = history_machine();
+        history_machine.filter =
+            Some(|action, _from, _to| !matches!(action, Action::Increment(_)));
+
+        history_machine.transition(&Action::Decrement(0));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Finish);
+        assert_eq!(history_machine.past.len(), 2);
+        assert_eq!(history_machine.machine.context.count, 2);
+
+        history_machine.undo();
+        assert_eq!(history_machine.machine.value, State::Active);
+        assert_eq!(history_machine.machine.context.count, 2);
+
+        // Unrecorded transitions keep the redo stack while the state is unchanged
+        history_machine.transition(&Action::Increment(1));
+        assert_eq!(history_machine.future.len(), 1);
+
+        history_machine.filter = Some(|action, _from, _to| matches!(action, Action::Decrement(_)));
+        history_machine.transition(&Action::Finish);
+        assert_eq!(history_machine.machine.value, State::Finished);
+        assert_eq!(history_machine.past.len(), 1);
+        assert!(history_machine.future.is_empty());
+    }
+
+    #[test]
+    fn filter_on_state_change() {
+        let mut history_machine = history_machine();
+        history_machine.filter = Some(|_action, from, to| from != to);
+
+        history_machine.transition(&Action::Finish);
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Start);
+        history_machine.transition(&Action::Increment(1));
+        let actions: Vec<_> = history_machine.log().map(|entry| entry.action).collect();
+        assert_eq!(actions, vec![Some(Action::Finish), Some(Action::Start)]);
+    }
+
+    #[test]
+    fn skip_unchanged() {
+        let mut history_machine = history_machine();
+        history_machine.skip_unchanged(true);
+
+        history_machine.transition(&Action::Increment(0));
+        history_machine.transition(&Action::Finish);
+        history_machine.transition(&Action::Increment(0));
+        history_machine.transition(&Action::Start);
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(0));
+        let actions: Vec<_> = history_machine.log().map(|entry| entry.action).collect();
+        let recorded = vec![Some(Action::Finish), Some(Action::Start), Some(Action::Increment(1))];
+        assert_eq!(actions, recorded);
+
+        history_machine.skip_unchanged(false);
+        history_machine.transition(&Action::Increment(0));
+        assert_eq!(history_machine.past.len(), 4);
+    }
+
+    #[test]
+    fn replay_unrecorded() {
+        let mut history_machine = history_machine();
+        history_machine.filter =
+            Some(|action, _from, _to| !matches!(action, Action::Increment(_)));
+
+        history_machine.transition(&Action::Decrement(0));
+        history_machine.transition(&Action::Increment(5));
+        history_machine.transition(&Action::Finish);
+        history_machine.transition(&Action::Start);
+        history_machine.transition(&Action::Increment(6));
+        assert_eq!(history_machine.past.len(), 3);
+        assert_eq!(history_machine.past[0].unrecorded, vec![Action::Increment(5)]);
+
+        // Filtered actions are replayed after the step they followed
+        history_machine.machine.context.count = 0;
+        assert_eq!(history_machine.replay(true), Ok(()));
+        assert_eq!(history_machine.machine.context.count, 11);
+    }
+}
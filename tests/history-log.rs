@@ -0,0 +1,63 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use rstate::*;
+
+    #[test]
+    fn action_log() {
+        let mut history_machine = HistoryMachine::<Action, State, Context>::new(counter("counter"));
+
+        history_machine.transition(&Action::Increment(3));
+        history_machine.transition(&Action::Decrement(1));
+        history_machine.transition(&Action::Increment(5));
+        history_machine.undo();
+
+        let log: Vec<_> = history_machine.log().collect();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, Some(Action::Increment(3)));
+        assert_eq!(log[0].snapshot.context.count, 0);
+        assert_eq!(log[1].action, Some(Action::Decrement(1)));
+        assert_eq!(log[1].snapshot.context.count, 3);
+        assert!(log[0].seq < log[1].seq);
+        assert!(log[0].timestamp <= log[1].timestamp);
+
+        // Redone entries keep their sequence number
+        history_machine.redo();
+        let last = history_machine.log().last().unwrap();
+        assert_eq!(last.action, Some(Action::Increment(5)));
+        assert_eq!(last.seq, 2);
+    }
+
+    #[test]
+    fn replay() {
+        let mut history_machine = HistoryMachine::<Action, State, Context>::new(counter("counter"));
+
+        history_machine.transition(&Action::Increment(3));
+        history_machine.transition(&Action::Decrement(1));
+        history_machine.transition(&Action::Increment(5));
+
+        history_machine.machine.context.count = 0;
+        assert_eq!(history_machine.replay(true), Ok(()));
+        assert_eq!(history_machine.machine.context.count, 7);
+        assert_eq!(history_machine.past.len(), 3);
+    }
+
+    #[test]
+    fn replay_after_eviction() {
+        let mut history_machine =
+            HistoryMachine::<Action, State, Context>::with_capacity(counter("counter"), 2);
+
+        history_machine.transition(&Action::Increment(3));
+        history_machine.transition(&Action::Decrement(1));
+        history_machine.transition(&Action::Increment(5));
+
+        assert_eq!(history_machine.replay(true), Err(Error::HistoryEvicted(1)));
+
+        history_machine.machine.context.count = 0;
+        assert_eq!(history_machine.replay(false), Ok(()));
+        assert_eq!(history_machine.machine.context.count, 7);
+    }
+}
@@ -0,0 +1,127 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Power,
+        Play,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    enum State {
+        Off,
+        On,
+        Resume,
+        Stopped,
+        Playing,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+    struct Context {}
+
+    impl EstimateSize for Context {
+        fn estimated_size(&self) -> usize {
+            0
+        }
+    }
+
+    fn player() -> Machine<Action, State, Context> {
+        let mut machine =
+            Machine::<Action, State, Context>::new("player".to_string(), State::Resume, Context {});
+        machine
+            .add_history_state(State::Resume, HistoryState { target: State::Stopped, deep: false });
+        machine.add_state(
+            State::Stopped,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Play => State::Playing,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(State::Playing, Transition::default());
+        machine
+    }
+
+    /// Television whose screen resumes the player where it was when the power went off
+    fn tv() -> Machine<Action, State, Context> {
+        let mut machine =
+            Machine::<Action, State, Context>::new("tv".to_string(), State::Off, Context {});
+        let power = Transition {
+            on: Some(|_context, action, state| match (action, state) {
+                (Action::Power, State::Off) => State::On,
+                (Action::Power, _) => State::Off,
+                _ => state,
+            }),
+            ..Default::default()
+        };
+        machine.add_state(State::Off, power);
+        machine.add_parallel_state(
+            State::On,
+            power,
+            ParallelMachine::new("screen".to_string(), vec![player()]),
+        );
+        machine
+    }
+
+    #[test]
+    fn resume_last_state() {
+        let mut machine = tv();
+        machine.transition(&Action::Power);
+        assert_eq!(machine.configuration(), vec![State::On, State::Stopped]);
+
+        machine.transition(&Action::Play);
+        machine.transition(&Action::Power);
+        assert_eq!(machine.value, State::Off);
+
+        machine.transition(&Action::Power);
+        assert_eq!(machine.configuration(), vec![State::On, State::Playing]);
+    }
+
+    #[test]
+    fn restore_last_state() {
+        let mut machine = tv();
+        machine.transition(&Action::Power);
+        machine.transition(&Action::Play);
+        machine.transition(&Action::Power);
+
+        let mut restored = tv();
+        restored.restore_state(machine.save_state());
+        restored.transition(&Action::Power);
+        assert_eq!(restored.configuration(), vec![State::On, State::Playing]);
+    }
+
+    #[test]
+    fn undo_last_state() {
+        let mut machine = HistoryMachine::<Action, State, Context>::new(tv());
+        machine.transition(&Action::Power);
+        machine.transition(&Action::Play);
+        machine.transition(&Action::Power);
+        machine.undo();
+        machine.undo();
+        machine.undo();
+
+        // The player was never exited before the first step
+        machine.transition(&Action::Power);
+        assert_eq!(machine.machine.configuration(), vec![State::On, State::Stopped]);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn snapshot_last_state() {
+        let mut machine = tv();
+        machine.transition(&Action::Power);
+        machine.transition(&Action::Play);
+        machine.transition(&Action::Power);
+
+        let json = serde_json::to_string(&machine.to_snapshot()).unwrap();
+        let snapshot = serde_json::from_str(&json).unwrap();
+        let mut restored = Machine::from_snapshot(machine.definition.clone(), snapshot).unwrap();
+        restored.transition(&Action::Power);
+        assert_eq!(restored.configuration(), vec![State::On, State::Playing]);
+    }
+}
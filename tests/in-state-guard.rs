@@ -0,0 +1,128 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[test]
+    fn in_state_guard() {
+        #[derive(Copy, Clone, Debug)]
+        enum Action {
+            Bullets,
+            None,
+            ToggleBold,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ToggleState {
+            On,
+            Off,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ListState {
+            None,
+            Bullets,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum State {
+            Bold(ToggleState),
+            List(ListState),
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        struct Context {}
+
+        let context = Context {};
+
+        let mut bold_machine = Machine::<Action, State, Context>::new(
+            "bold".to_string(),
+            State::Bold(ToggleState::Off),
+            context,
+        );
+        let mut list_machine = Machine::<Action, State, Context>::new(
+            "list".to_string(),
+            State::List(ListState::None),
+            context,
+        );
+
+        // ToggleBold is only allowed when List is None
+        bold_machine.add_state(
+            State::Bold(ToggleState::Off),
+            Transition {
+                guard: Some(|_context, _action, active| {
+                    active.contains(&State::List(ListState::None))
+                }),
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => State::Bold(ToggleState::On),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        bold_machine.add_state(
+            State::Bold(ToggleState::On),
+            Transition {
+                guard: Some(|_context, _action, active| {
+                    active.contains(&State::List(ListState::None))
+                }),
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => State::Bold(ToggleState::Off),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+
+        list_machine.add_state(
+            State::List(ListState::None),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Bullets => State::List(ListState::Bullets),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        list_machine.add_state(
+            State::List(ListState::Bullets),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::None => State::List(ListState::None),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "parallel".to_string(),
+            vec![bold_machine, list_machine],
+        );
+
+        machine.transition(&Action::ToggleBold);
+        assert_eq!(
+            machine.value,
+            vec![State::Bold(ToggleState::On), State::List(ListState::None)]
+        );
+
+        machine.transition(&Action::Bullets);
+        assert_eq!(
+            machine.value,
+            vec![State::Bold(ToggleState::On), State::List(ListState::Bullets)]
+        );
+
+        // Blocked by the guard while the list region is in Bullets
+        machine.transition(&Action::ToggleBold);
+        assert_eq!(
+            machine.value,
+            vec![State::Bold(ToggleState::On), State::List(ListState::Bullets)]
+        );
+
+        machine.transition(&Action::None);
+        machine.transition(&Action::ToggleBold);
+        assert_eq!(
+            machine.value,
+            vec![State::Bold(ToggleState::Off), State::List(ListState::None)]
+        );
+    }
+}
@@ -0,0 +1,123 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+    use std::sync::Arc;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Pay,
+        Ship,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Pending,
+        Paid,
+        Shipped,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Context {
+        order: u32,
+    }
+
+    fn order_workflow() -> Arc<MachineDefinition<Action, State, Context>> {
+        let mut definition = MachineDefinition::new("order".to_string(), State::Pending);
+        definition.add_state(
+            State::Pending,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Pay => State::Paid,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        definition.add_state(
+            State::Paid,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Ship => State::Shipped,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        definition.add_state(
+            State::Shipped,
+            Transition { final_state: true, ..Default::default() },
+        );
+        Arc::new(definition)
+    }
+
+    #[test]
+    fn instances_share_definition() {
+        let definition = order_workflow();
+        let mut orders: Vec<_> = (0..1000)
+            .map(|order| Machine::from_definition(definition.clone(), Context { order }))
+            .collect();
+
+        assert_eq!(Arc::strong_count(&definition), 1001);
+        assert!(Arc::ptr_eq(&orders[0].definition, &orders[999].definition));
+
+        orders[1].transition(&Action::Pay);
+        orders[2].transition(&Action::Pay);
+        orders[2].transition(&Action::Ship);
+
+        assert_eq!(orders[0].value, State::Pending);
+        assert_eq!(orders[1].value, State::Paid);
+        assert!(orders[2].is_done());
+        assert_eq!(orders[2].context, Context { order: 2 });
+        assert_eq!(orders[2].id, "order");
+    }
+
+    #[test]
+    fn changing_an_instance_copies_definition() {
+        let definition = order_workflow();
+        let mut first = Machine::from_definition(definition.clone(), Context { order: 1 });
+        let second = Machine::from_definition(definition.clone(), Context { order: 2 });
+
+        first.add_state(State::Shipped, Transition::default());
+
+        assert!(!Arc::ptr_eq(&first.definition, &definition));
+        assert!(Arc::ptr_eq(&second.definition, &definition));
+        assert!(definition.states[&State::Shipped].final_state);
+        assert!(!first.definition.states[&State::Shipped].final_state);
+    }
+
+    #[test]
+    fn instances_build_their_regions() {
+        let mut payment = Machine::<Action, State, Context>::new(
+            "payment".to_string(),
+            State::Pending,
+            Context { order: 0 },
+        );
+        payment.add_state(
+            State::Pending,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Pay => State::Paid,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        let regions = ParallelMachine::new("checkout".to_string(), vec![payment]);
+        let mut definition = MachineDefinition::new("order".to_string(), State::Paid);
+        definition.add_parallel_state(State::Paid, Transition::default(), regions.definition());
+        let definition = Arc::new(definition);
+
+        let mut first = Machine::from_definition(definition.clone(), Context { order: 1 });
+        let second = Machine::from_definition(definition.clone(), Context { order: 2 });
+        first.transition(&Action::Pay);
+        assert_eq!(first.configuration(), vec![State::Paid, State::Paid]);
+        assert_eq!(second.configuration(), vec![State::Paid, State::Pending]);
+
+        // Regions are built from the shared definition of each region machine
+        let region = &definition.regions[&State::Paid].regions[0];
+        assert!(Arc::ptr_eq(&region.definition, &regions.machines[0].definition));
+        let instance = &second.regions[&State::Paid].machines[0];
+        assert!(Arc::ptr_eq(&instance.definition, &region.definition));
+        assert_eq!(instance.id, "payment");
+    }
+}
@@ -0,0 +1,219 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[test]
+    fn nested_parallel_machine() {
+        #[derive(Copy, Clone, Debug)]
+        enum Action {
+            Lock,
+            Unlock,
+            Bullets,
+            ToggleBold,
+            ToggleItalics,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ToggleState {
+            On,
+            Off,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum ListState {
+            None,
+            Bullets,
+        }
+
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        enum State {
+            Editing,
+            ReadOnly,
+            Bold(ToggleState),
+            Italics(ToggleState),
+            List(ListState),
+        }
+
+        #[derive(Debug, Clone, Copy)]
+        struct Context {
+            exits: u8,
+        }
+
+        let context = Context { exits: 0 };
+
+        let mut bold_machine = Machine::<Action, State, Context>::new(
+            "bold".to_string(),
+            State::Bold(ToggleState::Off),
+            context,
+        );
+        let mut italics_machine = Machine::<Action, State, Context>::new(
+            "italics".to_string(),
+            State::Italics(ToggleState::Off),
+            context,
+        );
+        let mut list_machine = Machine::<Action, State, Context>::new(
+            "list".to_string(),
+            State::List(ListState::None),
+            context,
+        );
+
+        bold_machine.add_state(
+            State::Bold(ToggleState::Off),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => State::Bold(ToggleState::On),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        bold_machine.add_state(
+            State::Bold(ToggleState::On),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleBold => State::Bold(ToggleState::Off),
+                    _ => state,
+                }),
+                on_exit: Some(|mut context, _action, _state| {
+                    context.exits += 1;
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        italics_machine.add_state(
+            State::Italics(ToggleState::Off),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleItalics => State::Italics(ToggleState::On),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        italics_machine.add_state(
+            State::Italics(ToggleState::On),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::ToggleItalics => State::Italics(ToggleState::Off),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+
+        list_machine.add_state(
+            State::List(ListState::None),
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Bullets => State::List(ListState::Bullets),
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        list_machine.add_state(
+            State::List(ListState::Bullets),
+            Transition {
+                ..Default::default()
+            },
+        );
+
+        let regions = ParallelMachine::<Action, State, Context>::new(
+            "formatting".to_string(),
+            vec![bold_machine, italics_machine, list_machine],
+        );
+
+        let mut machine = Machine::<Action, State, Context>::new(
+            "editor".to_string(),
+            State::Editing,
+            context,
+        );
+        machine.add_parallel_state(
+            State::Editing,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Lock => State::ReadOnly,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+            regions,
+        );
+        machine.add_state(
+            State::ReadOnly,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Unlock => State::Editing,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+
+        let formatting = |machine: &Machine<Action, State, Context>| {
+            machine.regions[&State::Editing].value.clone()
+        };
+
+        assert_eq!(machine.value, State::Editing);
+        assert_eq!(
+            formatting(&machine),
+            vec![
+                State::Bold(ToggleState::Off),
+                State::Italics(ToggleState::Off),
+                State::List(ListState::None),
+            ]
+        );
+
+        machine.transition(&Action::ToggleBold);
+        machine.transition(&Action::Bullets);
+        assert_eq!(machine.value, State::Editing);
+        assert_eq!(
+            formatting(&machine),
+            vec![
+                State::Bold(ToggleState::On),
+                State::Italics(ToggleState::Off),
+                State::List(ListState::Bullets),
+            ]
+        );
+
+        // Leaving the compound state exits every region
+        machine.transition(&Action::Lock);
+        assert_eq!(machine.value, State::ReadOnly);
+        assert_eq!(machine.regions[&State::Editing].machines[0].context.exits, 1);
+
+        // Regions do not receive actions while their parent is inactive
+        machine.transition(&Action::ToggleItalics);
+        assert_eq!(
+            formatting(&machine),
+            vec![
+                State::Bold(ToggleState::On),
+                State::Italics(ToggleState::Off),
+                State::List(ListState::Bullets),
+            ]
+        );
+
+        // Entering the compound state again starts every region over
+        machine.transition(&Action::Unlock);
+        assert_eq!(machine.value, State::Editing);
+        assert_eq!(
+            formatting(&machine),
+            vec![
+                State::Bold(ToggleState::Off),
+                State::Italics(ToggleState::Off),
+                State::List(ListState::None),
+            ]
+        );
+
+        machine.transition(&Action::ToggleItalics);
+        assert_eq!(
+            formatting(&machine),
+            vec![
+                State::Bold(ToggleState::Off),
+                State::Italics(ToggleState::On),
+                State::List(ListState::None),
+            ]
+        );
+    }
+}
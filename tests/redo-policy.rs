@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use rstate::*;
+
+    #[test]
+    fn transition_discards_redo() {
+        let mut history_machine = history_machine();
+        assert_eq!(history_machine.redo_policy, RedoPolicy::Discard);
+
+        history_machine.transition(&Action::Increment(2));
+        history_machine.transition(&Action::Increment(3));
+        history_machine.undo();
+        assert_eq!(history_machine.future.len(), 1);
+
+        history_machine.transition(&Action::Decrement(1));
+        assert!(history_machine.future.is_empty());
+        assert_eq!(history_machine.machine.context.count, 1);
+
+        // Nothing left to redo
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 1);
+
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 2);
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 0);
+        history_machine.redo();
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 1);
+    }
+
+    #[test]
+    fn mixed_undo_transition_redo() {
+        let mut history_machine = history_machine();
+
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.undo();
+        history_machine.undo();
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 2);
+        assert_eq!(history_machine.future.len(), 1);
+
+        history_machine.transition(&Action::Increment(5));
+        assert_eq!(history_machine.machine.context.count, 7);
+        assert!(history_machine.future.is_empty());
+
+        history_machine.undo();
+        history_machine.transition(&Action::Decrement(2));
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 0);
+        assert_eq!(history_machine.past.len(), 3);
+    }
+
+    #[test]
+    fn keep_redo() {
+        let mut history_machine = history_machine();
+        history_machine.redo_policy = RedoPolicy::Keep;
+
+        history_machine.transition(&Action::Increment(2));
+        history_machine.transition(&Action::Increment(3));
+        history_machine.undo();
+        history_machine.transition(&Action::Decrement(1));
+        assert_eq!(history_machine.future.len(), 1);
+
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 5);
+    }
+}
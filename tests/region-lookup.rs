@@ -0,0 +1,109 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Enable,
+        Disable,
+        Toggle,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        On,
+        Off,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {
+        entered: u8,
+        exited: u8,
+    }
+
+    fn feature(id: &str) -> Machine<Action, State, Context> {
+        let mut machine = Machine::<Action, State, Context>::new(
+            id.to_string(),
+            State::Off,
+            Context { entered: 0, exited: 0 },
+        );
+        machine.add_state(
+            State::Off,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Toggle => State::On,
+                    _ => state,
+                }),
+                on_entry: Some(|mut context, _action, _state| {
+                    context.entered += 1;
+                    context
+                }),
+                on_exit: Some(|mut context, _action, _state| {
+                    context.exited += 1;
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            State::On,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Toggle => State::Off,
+                    _ => state,
+                }),
+                on_exit: Some(|mut context, _action, _state| {
+                    context.exited += 1;
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+        machine
+    }
+
+    #[test]
+    fn region_lookup() {
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "features".to_string(),
+            vec![feature("bold"), feature("italics")],
+        );
+
+        machine.region_mut("italics").unwrap().set_state(State::On);
+        assert_eq!(machine.region("bold").unwrap().value, State::Off);
+        assert_eq!(machine.region("italics").unwrap().value, State::On);
+        assert!(machine.region("list").is_none());
+
+        machine.transition(&Action::Toggle);
+        let value = machine.value_map();
+        assert_eq!(value.len(), 2);
+        assert_eq!(value["bold"], State::On);
+        assert_eq!(value["italics"], State::Off);
+    }
+
+    #[test]
+    fn add_and_remove_regions() {
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "features".to_string(),
+            vec![feature("bold")],
+        );
+
+        machine.add_region(feature("spellcheck"), &Action::Enable).unwrap();
+        assert_eq!(machine.value, vec![State::Off, State::Off]);
+        assert_eq!(machine.region("spellcheck").unwrap().context.entered, 1);
+
+        let duplicate = machine.add_region(feature("spellcheck"), &Action::Enable);
+        assert_eq!(duplicate, Err(Error::DuplicateRegion("spellcheck".to_string())));
+        assert_eq!(machine.value, vec![State::Off, State::Off]);
+        assert_eq!(machine.region("spellcheck").unwrap().context.entered, 1);
+
+        machine.transition(&Action::Toggle);
+        assert_eq!(machine.value_map()["spellcheck"], State::On);
+
+        let removed = machine.remove_region("spellcheck", &Action::Disable).unwrap();
+        assert_eq!(removed.value, State::On);
+        assert_eq!(removed.context.exited, 2);
+        assert_eq!(machine.value, vec![State::On]);
+        assert!(machine.remove_region("spellcheck", &Action::Disable).is_none());
+    }
+}
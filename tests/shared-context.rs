@@ -0,0 +1,102 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Double,
+        Increment,
+        Both,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Doubler,
+        Incrementer,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {
+        count: u8,
+    }
+
+    fn regions(context: Context) -> Vec<Machine<Action, State, Context>> {
+        let mut doubler = Machine::<Action, State, Context>::new(
+            "doubler".to_string(),
+            State::Doubler,
+            context,
+        );
+        doubler.add_state(
+            State::Doubler,
+            Transition {
+                context: Some(|mut context, action, _state| {
+                    match action {
+                        Action::Double | Action::Both => context.count *= 2,
+                        Action::Increment => {}
+                    }
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut incrementer = Machine::<Action, State, Context>::new(
+            "incrementer".to_string(),
+            State::Incrementer,
+            context,
+        );
+        incrementer.add_state(
+            State::Incrementer,
+            Transition {
+                context: Some(|mut context, action, _state| {
+                    match action {
+                        Action::Increment | Action::Both => context.count += 1,
+                        Action::Double => {}
+                    }
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        vec![doubler, incrementer]
+    }
+
+    #[test]
+    fn shared_context() {
+        let mut machine = ParallelMachine::<Action, State, Context>::with_context(
+            "shared".to_string(),
+            regions(Context { count: 0 }),
+            Context { count: 1 },
+        );
+
+        assert_eq!(machine.context.unwrap().count, 1);
+        assert_eq!(machine.machines[1].context.count, 1);
+
+        machine.transition(&Action::Increment);
+        assert_eq!(machine.context.unwrap().count, 2);
+
+        machine.transition(&Action::Double);
+        assert_eq!(machine.context.unwrap().count, 4);
+
+        // Regions update the shared context in order: (4 * 2) + 1
+        machine.transition(&Action::Both);
+        assert_eq!(machine.context.unwrap().count, 9);
+        for region in machine.machines.iter() {
+            assert_eq!(region.context.count, 9);
+        }
+    }
+
+    #[test]
+    fn local_context() {
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "local".to_string(),
+            regions(Context { count: 1 }),
+        );
+
+        machine.transition(&Action::Both);
+        assert!(machine.context.is_none());
+        assert_eq!(machine.machines[0].context.count, 2);
+        assert_eq!(machine.machines[1].context.count, 2);
+    }
+}
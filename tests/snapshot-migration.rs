@@ -0,0 +1,145 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use rstate::*;
+    use serde::{Deserialize, Serialize};
+    use serde_json::{json, Value};
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Next,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum State {
+        Green,
+        Amber,
+        Red,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    struct Context {
+        cycles: u32,
+        faults: u32,
+    }
+
+    fn light() -> Machine<Action, State, Context> {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "light".to_string(),
+            State::Green,
+            Context { cycles: 0, faults: 0 },
+        );
+        machine.add_state(
+            State::Green,
+            Transition { on: Some(|_context, _action, _state| State::Amber), ..Default::default() },
+        );
+        machine.add_state(
+            State::Amber,
+            Transition { on: Some(|_context, _action, _state| State::Red), ..Default::default() },
+        );
+        machine.add_state(
+            State::Red,
+            Transition { on: Some(|_context, _action, _state| State::Green), ..Default::default() },
+        );
+        machine
+    }
+
+    /// Version 2 renamed `Yellow` to `Amber`
+    fn rename_yellow(mut snapshot: Value) -> Value {
+        if snapshot["value"] == "Yellow" {
+            snapshot["value"] = json!("Amber");
+        }
+        snapshot
+    }
+
+    /// Version 3 added `faults` to the context
+    fn add_faults(mut snapshot: Value) -> Value {
+        snapshot["context"]["faults"] = json!(0);
+        snapshot
+    }
+
+    fn schema() -> SnapshotSchema<Value> {
+        let mut schema = SnapshotSchema::new(3);
+        schema.add_migration(1, rename_yellow);
+        schema.add_migration(2, add_faults);
+        schema
+    }
+
+    #[test]
+    fn migrate_old_snapshot() {
+        let stored = json!({
+            "version": 1,
+            "id": "light",
+            "snapshot": {
+                "id": "light",
+                "value": "Yellow",
+                "context": { "cycles": 4 },
+                "regions": []
+            }
+        });
+
+        let mut machine = light();
+        let versioned = serde_json::from_value(stored).unwrap();
+        assert_eq!(schema().load(&mut machine, versioned), Ok(()));
+        assert_eq!(machine.value, State::Amber);
+        assert_eq!(machine.context, Context { cycles: 4, faults: 0 });
+
+        machine.transition(&Action::Next);
+        assert_eq!(machine.value, State::Red);
+    }
+
+    #[test]
+    fn current_snapshot_round_trip() {
+        let mut machine = light();
+        machine.transition(&Action::Next);
+
+        let json = serde_json::to_string(&schema().save(&machine)).unwrap();
+        assert!(json.contains("\"version\":3"));
+
+        let mut restored = light();
+        let versioned = serde_json::from_str(&json).unwrap();
+        assert_eq!(schema().load(&mut restored, versioned), Ok(()));
+        assert_eq!(restored.value, State::Amber);
+    }
+
+    #[test]
+    fn missing_migration() {
+        let mut schema = SnapshotSchema::<Value>::new(3);
+        schema.add_migration(2, add_faults);
+
+        let versioned =
+            VersionedSnapshot { version: 1, id: "light".to_string(), snapshot: json!({}) };
+        assert_eq!(schema.migrate(versioned), Err(Error::MigrationNotFound(1)));
+    }
+
+    #[test]
+    fn newer_version() {
+        let schema = SnapshotSchema::<Value>::new(3);
+        let mut machine = light();
+        let newer = VersionedSnapshot { version: 4, id: "light".to_string(), snapshot: json!({}) };
+
+        assert_eq!(schema.migrate(newer.clone()), Err(Error::UnsupportedVersion(4)));
+        assert_eq!(schema.load(&mut machine, newer), Err(Error::UnsupportedVersion(4)));
+        assert_eq!(
+            Error::UnsupportedVersion(4).to_string(),
+            "snapshot version 4 is newer than the schema"
+        );
+    }
+
+    #[test]
+    fn mismatched_id_or_shape() {
+        let mut machine = light();
+
+        let other = json!({ "version": 3, "id": "door", "snapshot": {} });
+        let result = schema().load(&mut machine, serde_json::from_value(other).unwrap());
+        assert_eq!(result, Err(Error::SnapshotMismatch("light".to_string())));
+
+        let unknown = json!({
+            "version": 3,
+            "id": "light",
+            "snapshot": { "id": "light", "value": "Blue", "context": {}, "regions": [] }
+        });
+        let result = schema().load(&mut machine, serde_json::from_value(unknown).unwrap());
+        assert!(matches!(result, Err(Error::InvalidSnapshot(_))));
+        assert_eq!(machine.value, State::Green);
+    }
+}
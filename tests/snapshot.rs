@@ -0,0 +1,144 @@
+#[cfg(all(test, feature = "serde"))]
+mod tests {
+    use rstate::*;
+    use serde::{Deserialize, Serialize};
+    use std::sync::Arc;
+
+    #[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+    enum Action {
+        Next,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+    enum State {
+        Green,
+        Yellow,
+        Red,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+    struct Context {
+        cycles: u32,
+    }
+
+    fn light() -> Arc<MachineDefinition<Action, State, Context>> {
+        let mut definition =
+            MachineDefinition::<Action, State, Context>::new("light".to_string(), State::Green);
+        definition.add_state(
+            State::Green,
+            Transition {
+                on: Some(|_context, _action, _state| State::Yellow),
+                ..Default::default()
+            },
+        );
+        definition.add_state(
+            State::Yellow,
+            Transition { on: Some(|_context, _action, _state| State::Red), ..Default::default() },
+        );
+        definition.add_state(
+            State::Red,
+            Transition {
+                on: Some(|_context, _action, _state| State::Green),
+                context: Some(|mut context, _action, _state| {
+                    context.cycles += 1;
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+        Arc::new(definition)
+    }
+
+    #[test]
+    fn machine_round_trip() {
+        let definition = light();
+        let mut machine = Machine::from_definition(definition.clone(), Context { cycles: 0 });
+        for _ in 0..4 {
+            machine.transition(&Action::Next);
+        }
+
+        let json = serde_json::to_string(&machine.to_snapshot()).unwrap();
+        let snapshot: MachineState<State, Context> = serde_json::from_str(&json).unwrap();
+        let restored = Machine::from_snapshot(definition, snapshot).unwrap();
+
+        assert_eq!(restored.value, State::Yellow);
+        assert_eq!(restored.context, Context { cycles: 1 });
+        assert!(Arc::ptr_eq(&restored.definition, &machine.definition));
+    }
+
+    #[test]
+    fn parallel_round_trip() {
+        let definition = light();
+        let regions = |first, second| {
+            ParallelMachine::new(
+                "lights".to_string(),
+                vec![
+                    Machine::from_definition(definition.clone(), Context { cycles: first }),
+                    Machine::from_definition(definition.clone(), Context { cycles: second }),
+                ],
+            )
+        };
+
+        let mut machine = regions(0, 5);
+        machine.transition(&Action::Next);
+        let json = serde_json::to_string(&machine.to_snapshot()).unwrap();
+
+        let mut restored = regions(0, 0);
+        restored.restore_from(serde_json::from_str(&json).unwrap()).unwrap();
+        assert_eq!(restored.value, vec![State::Yellow, State::Yellow]);
+        assert_eq!(restored.machines[1].context, Context { cycles: 5 });
+    }
+
+    #[test]
+    fn history_round_trip() {
+        let definition = light();
+        let mut machine = HistoryMachine::<Action, State, Context>::new(Machine::from_definition(
+            definition.clone(),
+            Context { cycles: 0 },
+        ));
+        machine.transition(&Action::Next);
+        machine.transition(&Action::Next);
+        machine.undo();
+
+        let json = serde_json::to_string(&machine.to_snapshot()).unwrap();
+        let mut restored = HistoryMachine::<Action, State, Context>::new(
+            Machine::from_definition(definition, Context { cycles: 0 }),
+        );
+        restored.restore_from(serde_json::from_str(&json).unwrap()).unwrap();
+
+        assert_eq!(restored.machine.value, State::Yellow);
+        assert_eq!(restored.past.len(), 1);
+        assert_eq!(restored.future.len(), 1);
+        assert_eq!(restored.redo(), Some((State::Red, Context { cycles: 0 })));
+        assert_eq!(restored.undo(), Some((State::Yellow, Context { cycles: 0 })));
+        assert_eq!(restored.undo(), Some((State::Green, Context { cycles: 0 })));
+    }
+
+    #[test]
+    fn mismatched_snapshot() {
+        let mut machine = Machine::from_definition(light(), Context { cycles: 0 });
+        let mut snapshot = machine.to_snapshot();
+        snapshot.regions.push((
+            State::Red,
+            ParallelState { id: "missing".to_string(), machines: vec![], context: None },
+        ));
+
+        let result = machine.restore_from(snapshot);
+        assert_eq!(result, Err(Error::SnapshotMismatch("light".to_string())));
+        assert_eq!(machine.value, State::Green);
+    }
+
+    #[test]
+    fn snapshot_of_another_machine() {
+        let definition = light();
+        let mut other = Machine::from_definition(definition.clone(), Context { cycles: 0 });
+        other.id = "other".to_string();
+        other.transition(&Action::Next);
+
+        let mut machine = Machine::from_definition(definition, Context { cycles: 0 });
+        let result = machine.restore_from(other.to_snapshot());
+        assert_eq!(result, Err(Error::SnapshotMismatch("light".to_string())));
+        assert_eq!(machine.id, "light");
+        assert_eq!(machine.value, State::Green);
+    }
+}
@@ -0,0 +1,139 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Action {
+        Toggle,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        On,
+        Off,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Context {
+        toggles: u32,
+    }
+
+    fn switch(id: &str, initial: State) -> Machine<Action, State, Context> {
+        let mut machine = Machine::<Action, State, Context>::new(
+            id.to_string(),
+            initial,
+            Context { toggles: 0 },
+        );
+        for state in [State::On, State::Off].iter() {
+            machine.add_state(
+                *state,
+                Transition {
+                    context: Some(|mut context, _action, _state| {
+                        context.toggles += 1;
+                        context
+                    }),
+                    on: Some(|_context, _action, state| match state {
+                        State::On => State::Off,
+                        State::Off => State::On,
+                    }),
+                    ..Default::default()
+                },
+            );
+        }
+        machine
+    }
+
+    fn toggle_twice<M: StateMachine<Action>>(machine: &mut M) -> M::Value {
+        machine.transition(&Action::Toggle);
+        machine.transition(&Action::Toggle);
+        machine.value()
+    }
+
+    #[test]
+    fn generic_over_machine_kinds() {
+        let mut machine = switch("switch", State::Off);
+        assert_eq!(toggle_twice(&mut machine), State::Off);
+
+        let mut machine = ParallelMachine::<Action, State, Context>::new(
+            "switches".to_string(),
+            vec![switch("first", State::On), switch("second", State::Off)],
+        );
+        assert_eq!(toggle_twice(&mut machine), vec![State::On, State::Off]);
+        assert_eq!(machine.context(), vec![Context { toggles: 2 }, Context { toggles: 2 }]);
+    }
+
+    #[test]
+    fn history_of_parallel_machine() {
+        let parallel = ParallelMachine::<Action, State, Context>::new(
+            "switches".to_string(),
+            vec![switch("first", State::On), switch("second", State::Off)],
+        );
+        let mut machine = HistoryMachine::new(parallel);
+
+        machine.transition(&Action::Toggle);
+        assert_eq!(machine.machine.value, vec![State::Off, State::On]);
+
+        let (value, context) = machine.undo().unwrap();
+        assert_eq!(value, vec![State::On, State::Off]);
+        assert_eq!(context, vec![Context { toggles: 0 }, Context { toggles: 0 }]);
+        assert_eq!(machine.machine.machines[0].value, State::On);
+
+        machine.redo();
+        assert_eq!(machine.value(), vec![State::Off, State::On]);
+    }
+
+    #[test]
+    fn nested_history() {
+        let inner = HistoryMachine::<Action, State, Context>::new(switch("switch", State::Off));
+        let mut machine = HistoryMachine::new(inner);
+
+        assert_eq!(toggle_twice(&mut machine), State::Off);
+        assert_eq!(machine.len(), 2);
+        assert_eq!(machine.machine.len(), 2);
+
+        machine.undo();
+        assert_eq!(machine.value(), State::On);
+        assert_eq!(machine.machine.len(), 2);
+    }
+
+    #[test]
+    fn history_restores_nested_regions() {
+        let mut editor = Machine::<Action, State, Context>::new(
+            "editor".to_string(),
+            State::On,
+            Context { toggles: 0 },
+        );
+        editor.add_parallel_state(
+            State::On,
+            Transition::default(),
+            ParallelMachine::new("format".to_string(), vec![switch("bold", State::Off)]),
+        );
+        let mut machine = HistoryMachine::<Action, State, Context>::new(editor);
+
+        machine.transition(&Action::Toggle);
+        assert_eq!(machine.machine.configuration(), vec![State::On, State::On]);
+
+        machine.undo();
+        assert_eq!(machine.machine.configuration(), vec![State::On, State::Off]);
+        assert_eq!(machine.machine.regions[&State::On].machines[0].context.toggles, 0);
+
+        machine.redo();
+        assert_eq!(machine.machine.configuration(), vec![State::On, State::On]);
+    }
+
+    #[test]
+    fn undo_tree_of_parallel_machine() {
+        let parallel = ParallelMachine::<Action, State, Context>::new(
+            "switches".to_string(),
+            vec![switch("first", State::On), switch("second", State::Off)],
+        );
+        let mut machine = UndoTreeMachine::new(parallel);
+
+        machine.transition(&Action::Toggle);
+        assert_eq!(machine.machine.value, vec![State::Off, State::On]);
+
+        machine.undo();
+        assert_eq!(machine.machine.value, vec![State::On, State::Off]);
+        assert_eq!(machine.machine.machines[1].context.toggles, 0);
+    }
+}
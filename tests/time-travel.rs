@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Action {
+        Increment(u8),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Active,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {
+        count: u8,
+    }
+
+    #[test]
+    fn time_travel() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "increment".to_string(),
+            State::Active,
+            Context { count: 0 },
+        );
+        machine.add_state(
+            State::Active,
+            Transition {
+                context: Some(|mut context, action, _state| {
+                    match action {
+                        Action::Increment(val) => context.count += val,
+                    }
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut history_machine = HistoryMachine::<Action, State, Context>::new(machine);
+        assert!(history_machine.is_empty());
+        assert_eq!(history_machine.cursor(), 0);
+
+        for val in 1..=4 {
+            history_machine.transition(&Action::Increment(val));
+        }
+        assert_eq!(history_machine.len(), 4);
+        assert_eq!(history_machine.cursor(), 4);
+
+        assert_eq!(history_machine.goto(1), Ok(()));
+        assert_eq!(history_machine.cursor(), 1);
+        assert_eq!(history_machine.machine.context.count, 1);
+        assert_eq!(history_machine.len(), 4);
+
+        let counts: Vec<u8> = history_machine.timeline().map(|entry| entry.context.count).collect();
+        assert_eq!(counts, vec![0, 1, 3, 6, 10]);
+
+        let actions: Vec<Option<Action>> =
+            history_machine.timeline().map(|entry| entry.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                None,
+                Some(Action::Increment(1)),
+                Some(Action::Increment(2)),
+                Some(Action::Increment(3)),
+                Some(Action::Increment(4)),
+            ]
+        );
+
+        assert_eq!(history_machine.goto(3), Ok(()));
+        assert_eq!(history_machine.machine.context.count, 6);
+        assert_eq!(history_machine.at(4).unwrap().context.count, 10);
+        assert!(history_machine.at(5).is_none());
+
+        assert_eq!(history_machine.goto(5), Err(Error::IndexOutOfRange(5)));
+        assert_eq!(history_machine.cursor(), 3);
+
+        history_machine.goto(0).unwrap();
+        assert_eq!(history_machine.machine.context.count, 0);
+        history_machine.goto(4).unwrap();
+        assert_eq!(history_machine.machine.context.count, 10);
+    }
+}
@@ -16,6 +16,7 @@ mod tests {
 
     #[derive(Debug, Clone, Copy)]
     struct Context {
+        #[allow(dead_code)]
         entered: bool,
         count: u8,
     }
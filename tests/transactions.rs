@@ -0,0 +1,94 @@
+#[cfg(test)]
+mod common;
+
+#[cfg(test)]
+mod tests {
+    use crate::common::*;
+    use std::time::Duration;
+
+    #[test]
+    fn begin_and_end_group() {
+        let mut history_machine = history_machine();
+
+        history_machine.transition(&Action::Increment(1));
+        history_machine.begin_group();
+        history_machine.transition(&Action::Increment(1));
+        history_machine.begin_group();
+        history_machine.transition(&Action::Increment(1));
+        history_machine.end_group();
+        history_machine.transition(&Action::Increment(1));
+        history_machine.end_group();
+        history_machine.transition(&Action::Increment(1));
+        assert_eq!(history_machine.machine.context.count, 5);
+
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 4);
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 1);
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 0);
+
+        history_machine.redo();
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 4);
+        assert_eq!(history_machine.past.len(), 4);
+    }
+
+    #[test]
+    fn transaction() {
+        let mut history_machine = history_machine();
+
+        let count = history_machine.transaction(|machine| {
+            for _ in 0..5 {
+                machine.transition(&Action::Increment(2));
+            }
+            machine.machine.context.count
+        });
+        assert_eq!(count, 10);
+
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 0);
+        history_machine.redo();
+        assert_eq!(history_machine.machine.context.count, 10);
+    }
+
+    #[test]
+    fn coalesce_rapid_actions() {
+        let mut history_machine = history_machine();
+        history_machine.coalesce = Some(Duration::from_secs(60));
+
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 0);
+
+        history_machine.redo();
+        history_machine.coalesce = Some(Duration::from_secs(0));
+        std::thread::sleep(Duration::from_millis(5));
+        history_machine.transition(&Action::Increment(1));
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 3);
+    }
+
+    #[test]
+    fn coalesce_after_transaction() {
+        let mut history_machine = history_machine();
+        history_machine.coalesce = Some(Duration::from_secs(60));
+
+        history_machine.transaction(|machine| {
+            machine.transition(&Action::Increment(1));
+            machine.transition(&Action::Increment(1));
+        });
+        history_machine.transition(&Action::Increment(5));
+        history_machine.transition(&Action::Increment(5));
+        let groups: Vec<_> = history_machine.log().map(|entry| entry.group).collect();
+        assert_eq!(groups, vec![0, 0, 2, 2]);
+
+        // The closed transaction is not extended by the actions coalesced after it
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 2);
+        history_machine.undo();
+        assert_eq!(history_machine.machine.context.count, 0);
+    }
+}
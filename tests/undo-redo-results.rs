@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static ENTRIES: AtomicUsize = AtomicUsize::new(0);
+    static EXITS: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(Debug, Copy, Clone)]
+    enum Action {
+        Toggle,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Active,
+        Inactive,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Context {
+        count: u8,
+    }
+
+    #[test]
+    fn undo_redo_results() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "toggle".to_string(),
+            State::Inactive,
+            Context { count: 0 },
+        );
+        machine.add_state(
+            State::Inactive,
+            Transition {
+                on: Some(|_context, action, _state| match action {
+                    Action::Toggle => State::Active,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(
+            State::Active,
+            Transition {
+                on: Some(|_context, action, _state| match action {
+                    Action::Toggle => State::Inactive,
+                }),
+                on_entry: Some(|mut context, _action, _state| {
+                    ENTRIES.fetch_add(1, Ordering::SeqCst);
+                    context.count += 1;
+                    context
+                }),
+                on_exit: Some(|context, _action, _state| {
+                    EXITS.fetch_add(1, Ordering::SeqCst);
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        let events = Rc::new(RefCell::new(vec![]));
+        let mut history_machine = HistoryMachine::<Action, State, Context>::new(machine);
+        let log = Rc::clone(&events);
+        history_machine.subscribe(move |event| log.borrow_mut().push(*event));
+
+        assert!(!history_machine.can_undo());
+        assert!(!history_machine.can_redo());
+        assert_eq!(history_machine.undo(), None);
+        assert_eq!(history_machine.redo(), None);
+        assert!(events.borrow().is_empty());
+
+        history_machine.transition(&Action::Toggle);
+        assert_eq!(ENTRIES.load(Ordering::SeqCst), 1);
+        assert!(history_machine.can_undo());
+
+        // Hooks only run on undo and redo when asked to
+        assert_eq!(history_machine.undo(), Some((State::Inactive, Context { count: 0 })));
+        assert_eq!(EXITS.load(Ordering::SeqCst), 0);
+        assert!(history_machine.can_redo());
+
+        history_machine.run_hooks = true;
+        assert_eq!(history_machine.redo(), Some((State::Active, Context { count: 1 })));
+        assert_eq!(ENTRIES.load(Ordering::SeqCst), 2);
+        assert_eq!(history_machine.machine.context.count, 1);
+
+        history_machine.undo();
+        assert_eq!(EXITS.load(Ordering::SeqCst), 1);
+
+        history_machine.checkpoint("start");
+        assert_eq!(history_machine.goto(1), Ok(()));
+        assert_eq!(history_machine.restore("start"), Ok(()));
+
+        let inactive = Context { count: 0 };
+        let active = Context { count: 1 };
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                HistoryEvent::Undo(State::Inactive, inactive),
+                HistoryEvent::Redo(State::Active, active),
+                HistoryEvent::Undo(State::Inactive, inactive),
+                HistoryEvent::Goto(State::Active, active),
+                HistoryEvent::Restore(State::Inactive, inactive),
+            ]
+        );
+    }
+}
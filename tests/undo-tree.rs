@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod tests {
+    use rstate::*;
+
+    #[derive(Debug, Copy, Clone, PartialEq)]
+    enum Action {
+        Increment(u8),
+        Decrement(u8),
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Active,
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    struct Context {
+        count: u8,
+    }
+
+    #[test]
+    fn undo_tree_machine() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "increment".to_string(),
+            State::Active,
+            Context { count: 0 },
+        );
+        machine.add_state(
+            State::Active,
+            Transition {
+                context: Some(|mut context, action, _state| {
+                    match action {
+                        Action::Increment(val) => context.count += val,
+                        Action::Decrement(val) => context.count -= val,
+                    }
+                    context
+                }),
+                ..Default::default()
+            },
+        );
+
+        let mut tree = UndoTreeMachine::<Action, State, Context>::new(machine);
+
+        tree.transition(&Action::Increment(2)); // node 1
+        tree.transition(&Action::Increment(3)); // node 2
+        assert_eq!(tree.machine.context.count, 5);
+
+        // Branch off after undoing, keeping the undone step
+        tree.undo();
+        tree.transition(&Action::Decrement(1)); // node 3
+        assert_eq!(tree.machine.context.count, 1);
+        assert_eq!(tree.branches(1), &[2, 3]);
+        assert_eq!(tree.nodes[3].action, Some(Action::Decrement(1)));
+
+        // Navigate between sibling branches
+        tree.previous_branch();
+        assert_eq!(tree.current, 2);
+        assert_eq!(tree.machine.context.count, 5);
+        tree.previous_branch();
+        assert_eq!(tree.current, 2);
+        tree.next_branch();
+        assert_eq!(tree.current, 3);
+        assert_eq!(tree.machine.context.count, 1);
+
+        // Redo follows the most recently visited branch
+        tree.undo();
+        tree.undo();
+        assert_eq!(tree.machine.context.count, 0);
+        tree.redo();
+        tree.redo();
+        assert_eq!(tree.current, 3);
+
+        // Jump anywhere in the tree
+        assert_eq!(tree.goto(2), Ok(()));
+        assert_eq!(tree.machine.context.count, 5);
+        tree.goto(0).unwrap();
+        tree.redo();
+        tree.redo();
+        assert_eq!(tree.current, 2);
+        assert_eq!(tree.goto(9), Err(Error::NodeNotFound(9)));
+        assert_eq!(tree.current, 2);
+    }
+}
@@ -0,0 +1,353 @@
+#[cfg(all(test, feature = "xstate"))]
+mod tests {
+    use rstate::*;
+    use serde_json::json;
+
+    #[derive(Copy, Clone, Debug, PartialEq)]
+    enum Action {
+        Submit,
+        Approve,
+        Reject,
+        Ship,
+        Deliver,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+    enum State {
+        Draft,
+        Review,
+        Fulfilment,
+        Packing,
+        Packed,
+        Billing,
+        Billed,
+        Done,
+        Resume,
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Context {
+        submissions: u32,
+        rejections: u32,
+        reviewing: bool,
+    }
+
+    fn registry() -> XstateRegistry<Action, State, Context> {
+        let mut registry = XstateRegistry::<Action, State, Context>::new();
+        for (name, state) in [
+            ("draft", State::Draft),
+            ("review", State::Review),
+            ("fulfilment", State::Fulfilment),
+            ("packing", State::Packing),
+            ("packed", State::Packed),
+            ("billing", State::Billing),
+            ("billed", State::Billed),
+            ("done", State::Done),
+            ("resume", State::Resume),
+        ]
+        .iter()
+        {
+            registry.add_state(name, *state);
+        }
+        registry.add_event("SUBMIT", Action::Submit);
+        registry.add_event("APPROVE", Action::Approve);
+        registry.add_event("REJECT", Action::Reject);
+        registry.add_event("SHIP", Action::Ship);
+        registry.add_event("DELIVER", Action::Deliver);
+        registry.add_action("countSubmission", |mut context, _action, _state| {
+            context.submissions += 1;
+            context
+        });
+        registry.add_action("countRejection", |mut context, _action, _state| {
+            context.rejections += 1;
+            context
+        });
+        registry.add_action("startReview", |mut context, _action, _state| {
+            context.reviewing = true;
+            context
+        });
+        registry.add_action("stopReview", |mut context, _action, _state| {
+            context.reviewing = false;
+            context
+        });
+        registry.add_guard("underLimit", |context, _action, _active| context.submissions < 2);
+        registry
+    }
+
+    fn config() -> serde_json::Value {
+        json!({
+            "id": "order",
+            "initial": "draft",
+            "states": {
+                "draft": {
+                    "on": {
+                        "SUBMIT": {
+                            "target": "review",
+                            "cond": "underLimit",
+                            "actions": "countSubmission"
+                        }
+                    }
+                },
+                "review": {
+                    "entry": "startReview",
+                    "exit": ["stopReview"],
+                    "on": {
+                        "APPROVE": "fulfilment",
+                        "REJECT": { "target": "draft", "actions": ["countRejection"] }
+                    }
+                },
+                "fulfilment": {
+                    "type": "parallel",
+                    "states": {
+                        "shipping": {
+                            "initial": "packing",
+                            "states": {
+                                "packing": { "on": { "SHIP": "packed" } },
+                                "packed": { "type": "final" }
+                            }
+                        },
+                        "payment": {
+                            "initial": "billing",
+                            "states": {
+                                "billing": { "on": { "SHIP": "billed" } },
+                                "billed": { "type": "final" }
+                            }
+                        }
+                    },
+                    "on": { "DELIVER": "done" }
+                },
+                "done": { "type": "final" }
+            }
+        })
+    }
+
+    fn context() -> Context {
+        Context { submissions: 0, rejections: 0, reviewing: false }
+    }
+
+    #[test]
+    fn import_config() {
+        let mut machine = Machine::from_xstate(&config(), &registry(), context()).unwrap();
+        assert_eq!(machine.id, "order");
+
+        machine.transition(&Action::Submit);
+        assert_eq!(machine.value, State::Review);
+        assert!(machine.context.reviewing);
+
+        machine.transition(&Action::Reject);
+        assert_eq!(machine.value, State::Draft);
+        assert_eq!(machine.context.rejections, 1);
+        assert!(!machine.context.reviewing);
+
+        machine.transition(&Action::Submit);
+        machine.transition(&Action::Reject);
+        machine.transition(&Action::Submit);
+        assert_eq!(machine.value, State::Draft);
+
+        machine.context.submissions = 0;
+        machine.transition(&Action::Submit);
+        machine.transition(&Action::Approve);
+        assert_eq!(
+            machine.configuration(),
+            vec![State::Fulfilment, State::Billing, State::Packing]
+        );
+
+        machine.transition(&Action::Ship);
+        machine.transition(&Action::Deliver);
+        assert!(machine.is_done());
+    }
+
+    #[test]
+    fn export_round_trip() {
+        let machine = Machine::from_xstate(&config(), &registry(), context()).unwrap();
+        let exported = machine.to_xstate(&registry()).unwrap();
+
+        assert_eq!(exported["initial"], "draft");
+        assert_eq!(exported["states"]["review"]["entry"], "startReview");
+        assert_eq!(exported["states"]["review"]["on"]["APPROVE"], "fulfilment");
+        assert_eq!(exported["states"]["draft"]["on"]["SUBMIT"]["guard"], "underLimit");
+        assert_eq!(exported["states"]["fulfilment"]["type"], "parallel");
+        assert_eq!(exported["states"]["fulfilment"]["states"]["payment"]["initial"], "billing");
+        assert_eq!(exported["states"]["done"]["type"], "final");
+
+        let imported = Machine::from_xstate(&exported, &registry(), context()).unwrap();
+        assert_eq!(imported.to_xstate(&registry()).unwrap(), exported);
+    }
+
+    #[test]
+    fn export_on_functions() {
+        let mut machine = Machine::<Action, State, Context>::new(
+            "review".to_string(),
+            State::Review,
+            context(),
+        );
+        machine.add_state(
+            State::Review,
+            Transition {
+                on: Some(|_context, action, state| match action {
+                    Action::Approve => State::Done,
+                    Action::Reject => State::Draft,
+                    _ => state,
+                }),
+                ..Default::default()
+            },
+        );
+        machine.add_state(State::Draft, Transition::default());
+        machine.add_state(State::Done, Transition { final_state: true, ..Default::default() });
+
+        let exported = machine.to_xstate(&registry()).unwrap();
+        assert_eq!(
+            exported["states"]["review"]["on"],
+            json!({ "APPROVE": "done", "REJECT": "draft" })
+        );
+    }
+
+    fn history_config(history: &str) -> serde_json::Value {
+        json!({
+            "id": "order",
+            "initial": "draft",
+            "states": {
+                "draft": { "on": { "SUBMIT": "fulfilment" } },
+                "fulfilment": {
+                    "type": "parallel",
+                    "states": {
+                        "shipping": {
+                            "initial": "resume",
+                            "states": {
+                                "resume": {
+                                    "type": "history",
+                                    "history": history,
+                                    "target": "packing"
+                                },
+                                "packing": { "on": { "SHIP": "packed" } },
+                                "packed": {
+                                    "type": "parallel",
+                                    "states": {
+                                        "payment": {
+                                            "initial": "billing",
+                                            "states": {
+                                                "billing": { "on": { "DELIVER": "billed" } },
+                                                "billed": {}
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    },
+                    "on": { "REJECT": "draft" }
+                }
+            }
+        })
+    }
+
+    #[test]
+    fn shallow_history() {
+        let config = history_config("shallow");
+        let mut machine = Machine::from_xstate(&config, &registry(), context()).unwrap();
+
+        // Never exited, the region starts in the initial state of the history
+        machine.transition(&Action::Submit);
+        assert_eq!(machine.configuration(), vec![State::Fulfilment, State::Packing]);
+
+        machine.transition(&Action::Ship);
+        machine.transition(&Action::Deliver);
+        machine.transition(&Action::Reject);
+        machine.transition(&Action::Submit);
+        assert_eq!(
+            machine.configuration(),
+            vec![State::Fulfilment, State::Packed, State::Billing]
+        );
+    }
+
+    #[test]
+    fn deep_history() {
+        let config = history_config("deep");
+        let mut machine = Machine::from_xstate(&config, &registry(), context()).unwrap();
+
+        machine.transition(&Action::Submit);
+        machine.transition(&Action::Ship);
+        machine.transition(&Action::Deliver);
+        machine.transition(&Action::Reject);
+        assert_eq!(machine.value, State::Draft);
+
+        machine.transition(&Action::Submit);
+        assert_eq!(
+            machine.configuration(),
+            vec![State::Fulfilment, State::Packed, State::Billed]
+        );
+
+        let exported = machine.to_xstate(&registry()).unwrap();
+        let shipping = &exported["states"]["fulfilment"]["states"]["shipping"];
+        assert_eq!(
+            shipping["states"]["resume"],
+            json!({ "type": "history", "history": "deep", "target": "packing" })
+        );
+    }
+
+    #[test]
+    fn parallel_root() {
+        let config = json!({
+            "id": "fulfilment",
+            "type": "parallel",
+            "states": {
+                "shipping": {
+                    "initial": "packing",
+                    "states": {
+                        "packing": { "on": { "SHIP": "packed" } },
+                        "packed": { "type": "final" }
+                    }
+                },
+                "payment": {
+                    "initial": "billing",
+                    "states": {
+                        "billing": { "on": { "DELIVER": "billed" } },
+                        "billed": { "type": "final" }
+                    }
+                }
+            }
+        });
+        let mut machine = ParallelMachine::from_xstate(&config, &registry(), context()).unwrap();
+        assert_eq!(machine.value, vec![State::Billing, State::Packing]);
+
+        machine.transition(&Action::Ship);
+        assert_eq!(machine.value, vec![State::Billing, State::Packed]);
+        assert_eq!(machine.to_xstate(&registry()).unwrap(), config);
+
+        let result = Machine::from_xstate(&config, &registry(), context());
+        let message = "machine `fulfilment` is parallel".to_string();
+        assert_eq!(result.unwrap_err(), Error::InvalidConfig(message));
+    }
+
+    #[test]
+    fn unsupported_configs() {
+        let compound = json!({
+            "initial": "draft",
+            "states": {
+                "draft": {
+                    "initial": "review",
+                    "states": { "review": {} }
+                }
+            }
+        });
+        let result = Machine::from_xstate(&compound, &registry(), context());
+        let message = "compound state `draft` is not parallel".to_string();
+        assert_eq!(result.unwrap_err(), Error::InvalidConfig(message));
+
+        let history = json!({
+            "initial": "resume",
+            "states": { "draft": {}, "resume": { "type": "history" } }
+        });
+        let result = Machine::from_xstate(&history, &registry(), context());
+        let message = "initial history state `resume` has no target".to_string();
+        assert_eq!(result.unwrap_err(), Error::InvalidConfig(message));
+
+        let unknown = json!({
+            "initial": "draft",
+            "states": { "draft": { "on": { "CANCEL": "done" } } }
+        });
+        let result = Machine::from_xstate(&unknown, &registry(), context());
+        let message = "unknown event `CANCEL`".to_string();
+        assert_eq!(result.unwrap_err(), Error::InvalidConfig(message));
+    }
+}